@@ -0,0 +1,75 @@
+//! Throughput of the tokenizer and the parser on a large generated source
+//!
+//! Run with : cargo bench
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use rsblang::parser::Parser;
+use rsblang::tokenizer::Tokenizer;
+
+const FUNCTIONS: usize = 20_000;
+const ITERATIONS: u32 = 5;
+
+/**
+ * Generate a source of several megabytes, using all kinds of tokens
+ */
+fn generate_source() -> String {
+    let mut source = String::from("/* generated source */\nbuffer[100];\ncount 0;\n");
+
+    for i in 0..FUNCTIONS {
+        source.push_str(&format!(
+            "function_{i}(value, base) {{
+    extrn putchar, buffer, count;
+    auto index, digit 10;
+
+    index = 0;
+    while (value > 0 & index < 100) {{
+        digit = value % base;
+        buffer[index++] = digit < 10 ? digit + '0' : digit - 10 + 'a';
+        value =/ base;
+    }}
+    if (value == 0 | index != 0) count =+ {i};
+    switch (digit) {{
+        case 1: putchar(\"one*n\");
+        case 2: goto done;
+    }}
+done:
+    return (buffer[index - 1] << 2 >> 1);
+}}
+"
+        ));
+    }
+
+    source
+}
+
+fn measure<F: FnMut() -> usize>(name: &str, size: usize, mut run: F) {
+    let mut total = Duration::ZERO;
+    let mut count = 0;
+
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        count = black_box(run());
+        total += start.elapsed();
+    }
+
+    let average = total / ITERATIONS;
+    let throughput = size as f64 / average.as_secs_f64() / (1024.0 * 1024.0);
+
+    println!("{:<10} {:>10} items {:>10.2?} {:>8.2} MiB/s", name, count, average, throughput);
+}
+
+fn main() {
+    let source = generate_source();
+
+    println!("source: {:.2} MiB, {} iterations", source.len() as f64 / (1024.0 * 1024.0), ITERATIONS);
+
+    measure("tokenize", source.len(), || {
+        Tokenizer::new().stream(source.chars()).map(Result::unwrap).count()
+    });
+
+    measure("parse", source.len(), || {
+        Parser::new(Tokenizer::new().stream(source.chars())).parse_program().unwrap().functions.len()
+    });
+}
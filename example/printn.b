@@ -11,3 +11,8 @@ printn(n,b) {
                 printn(a, b); /* recursive */
         putchar(n%b + '0');
 }
+
+main() {
+        printn(1234, 10);
+        putchar('*n');
+}
@@ -0,0 +1,487 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+
+use rsblang::formatter::{self, BraceStyle, FormatOptions, Indent};
+use rsblang::layout::WordSize;
+use rsblang::parser::{Parser, Program};
+use rsblang::lowering;
+use rsblang::resolver::{Resolution, Resolver};
+use rsblang::tokenizer::{read_source, Dialect, ReadError, Tokenizer};
+use rsblang::vm::{self, Machine, RuntimeError};
+
+pub const USAGE: &str = "Usage: rsblang <command> [options] <files...>
+
+Commands:
+    tokens      Print the tokens of the source files
+    parse       Print the syntax tree of the source files
+    check       Check the syntax and the declarations of the source files
+    fmt         Print the source files in the canonical style
+    run         Execute the main function of the program
+    build       Compile the program, and print the opcodes of its functions
+
+Options:
+    -o <file>   Write the output to the given file instead of stdout
+    --check     With fmt, only report the files which are not formatted
+    --indent <n|tab>
+                With fmt, indent using n spaces (default 4) or tabs
+    --brace <same|next>
+                With fmt, put the opening braces on the same line (default) or the next one
+    --word <16|32|64>
+                With run, build and check, the size of the machine words in bits (default 64)
+    --dialect <historical|modern>
+                Tokenize a=-1 as the compound assignment a =- 1 (historical, default),
+                or as the assignment a = -1 (modern)
+    -h, --help  Display this help
+
+Use - as file name to read the source from stdin.
+
+Exit codes:
+    0  Success
+    1  The source contains errors, or is not formatted with fmt --check
+    2  Invalid command line arguments
+    3  Cannot read input or write output
+    4  The execution of the program failed";
+
+/**
+ * The command to execute
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Tokens,
+    Parse,
+    Check,
+    Format,
+    Run,
+    Build,
+}
+
+impl Command {
+    fn from_name(name: &str) -> Option<Command> {
+        match name {
+            "tokens" => Some(Command::Tokens),
+            "parse" => Some(Command::Parse),
+            "check" => Some(Command::Check),
+            "fmt" => Some(Command::Format),
+            "run" => Some(Command::Run),
+            "build" => Some(Command::Build),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * The parsed command line arguments
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub command: Command,
+    /**
+     * The source files. "-" means stdin
+     */
+    pub inputs: Vec<String>,
+    pub output: Option<String>,
+    pub format: FormatOptions,
+    /**
+     * Only report the files which are not formatted
+     */
+    pub check: bool,
+    /**
+     * Word size of the virtual machine
+     */
+    pub word_size: WordSize,
+    pub dialect: Dialect,
+}
+
+/**
+ * Result of the command line parsing, when no command should be executed
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UsageError {
+    /**
+     * The help was requested
+     */
+    Help,
+    Invalid(String),
+}
+
+impl Display for UsageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UsageError::Help => write!(f, "{}", USAGE),
+            UsageError::Invalid(message) => write!(f, "{}\n\n{}", message, USAGE),
+        }
+    }
+}
+
+/**
+ * Exit status of the compiler
+ * The statuses are ordered by severity, so the most severe failure of the inputs is reported
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Status {
+    Success = 0,
+    SourceError = 1,
+    Usage = 2,
+    Io = 3,
+    Runtime = 4,
+}
+
+impl From<Status> for ExitCode {
+    fn from(status: Status) -> Self {
+        ExitCode::from(status as u8)
+    }
+}
+
+impl Options {
+    /**
+     * Parse command line arguments, without the program name
+     */
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Options, UsageError> {
+        let mut args = args.into_iter();
+        let mut command = None;
+        let mut inputs = Vec::new();
+        let mut output = None;
+        let mut format = FormatOptions::default();
+        let mut check = false;
+        let mut word_size = WordSize::default();
+        let mut dialect = Dialect::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Err(UsageError::Help),
+                "-o" => match args.next() {
+                    Some(file) => output = Some(file),
+                    None => return Err(UsageError::Invalid(String::from("Missing file name after -o"))),
+                },
+                "--check" => check = true,
+                "--indent" => format.indent = match args.next().as_deref() {
+                    Some("tab") => Indent::Tab,
+                    Some(count) => match count.parse() {
+                        Ok(count) => Indent::Spaces(count),
+                        Err(_) => return Err(UsageError::Invalid(format!("Invalid indentation {}", count))),
+                    },
+                    None => return Err(UsageError::Invalid(String::from("Missing indentation after --indent"))),
+                },
+                "--brace" => format.brace_style = match args.next().as_deref() {
+                    Some("same") => BraceStyle::SameLine,
+                    Some("next") => BraceStyle::NextLine,
+                    Some(style) => return Err(UsageError::Invalid(format!("Invalid brace style {}", style))),
+                    None => return Err(UsageError::Invalid(String::from("Missing brace style after --brace"))),
+                },
+                "--word" => word_size = match args.next() {
+                    Some(bits) => match bits.parse().ok().and_then(WordSize::from_bits) {
+                        Some(size) => size,
+                        None => return Err(UsageError::Invalid(format!("Invalid word size {}", bits))),
+                    },
+                    None => return Err(UsageError::Invalid(String::from("Missing word size after --word"))),
+                },
+                "--dialect" => dialect = match args.next().as_deref() {
+                    Some("historical") => Dialect::Historical,
+                    Some("modern") => Dialect::Modern,
+                    Some(name) => return Err(UsageError::Invalid(format!("Invalid dialect {}", name))),
+                    None => return Err(UsageError::Invalid(String::from("Missing dialect after --dialect"))),
+                },
+                "-" => inputs.push(arg),
+                option if option.starts_with('-') => {
+                    return Err(UsageError::Invalid(format!("Unknown option {}", option)));
+                },
+                name if command.is_none() => match Command::from_name(name) {
+                    Some(c) => command = Some(c),
+                    None => return Err(UsageError::Invalid(format!("Unknown command {}", name))),
+                },
+                _ => inputs.push(arg),
+            }
+        }
+
+        let command = match command {
+            Some(command) => command,
+            None => return Err(UsageError::Invalid(String::from("Missing command"))),
+        };
+
+        if inputs.is_empty() {
+            return Err(UsageError::Invalid(String::from("Missing input file")));
+        }
+
+        // the formatter tokenizes the source too
+        format.dialect = dialect;
+
+        Ok(Options {
+            command,
+            inputs,
+            output,
+            format,
+            check,
+            word_size,
+            dialect,
+        })
+    }
+}
+
+/**
+ * Execute the command
+ * The output file is only written when the command succeeds, so a failure never truncates it
+ */
+pub fn execute(options: &Options) -> Status {
+    let mut buffer = Vec::new();
+    let mut stdout = io::stdout();
+    let mut status = Status::Success;
+
+    {
+        let out: &mut dyn Write = match options.output {
+            Some(_) => &mut buffer,
+            None => &mut stdout,
+        };
+
+        for input in &options.inputs {
+            let result = match options.command {
+                Command::Tokens => print_tokens(input, options.dialect, out),
+                Command::Parse => parse(input, options.dialect).and_then(|program| Ok(writeln!(out, "{:#?}", program)?)),
+                Command::Check => parse(input, options.dialect).and_then(|program| resolve(&program, options.word_size).map(|_| ())),
+                Command::Format => format(input, options, out),
+                Command::Run => run(input, options, out),
+                Command::Build => compile(input, options).and_then(|program| print_program(&program, out)),
+            };
+
+            if let Err(failure) = result {
+                failure.report(input);
+                status = status.max(failure.status());
+            }
+        }
+    }
+
+    match &options.output {
+        Some(file) if status == Status::Success => match fs::write(file, buffer) {
+            Ok(()) => status,
+            Err(e) => {
+                eprintln!("{}: {}", file, e);
+                Status::Io
+            },
+        },
+        _ => status,
+    }
+}
+
+/**
+ * Error raised when processing an input file
+ */
+enum Failure {
+    Io(Box<dyn Error>),
+    Source(Vec<Box<dyn Error>>),
+    /**
+     * The source is not formatted, with fmt --check
+     */
+    Unformatted,
+    Runtime(RuntimeError),
+}
+
+impl Failure {
+    fn report(&self, input: &str) {
+        match self {
+            Failure::Io(e) => eprintln!("{}: {}", input, e),
+            Failure::Source(errors) => errors.iter().for_each(|e| eprintln!("{}: {}", input, e)),
+            Failure::Unformatted => (),
+            Failure::Runtime(e) => eprintln!("{}: {}", input, e),
+        }
+    }
+
+    fn status(&self) -> Status {
+        match self {
+            Failure::Io(_) => Status::Io,
+            Failure::Source(_) | Failure::Unformatted => Status::SourceError,
+            Failure::Runtime(_) => Status::Runtime,
+        }
+    }
+}
+
+impl From<io::Error> for Failure {
+    fn from(e: io::Error) -> Self {
+        Failure::Io(e.into())
+    }
+}
+
+impl From<ReadError> for Failure {
+    fn from(e: ReadError) -> Self {
+        match e {
+            ReadError::Io(e) => Failure::Io(e.into()),
+            ReadError::Token(e) => Failure::Source(vec![e.into()]),
+        }
+    }
+}
+
+/**
+ * Open the source file, or stdin for "-"
+ */
+fn open(input: &str) -> Result<Box<dyn Read>, Failure> {
+    if input == "-" {
+        Ok(Box::new(io::stdin().lock()))
+    } else {
+        Ok(Box::new(File::open(input)?))
+    }
+}
+
+/**
+ * Parse the source while it's read
+ */
+fn parse(input: &str, dialect: Dialect) -> Result<Program, Failure> {
+    // an I/O error is not a source error, so it's kept apart from the parse errors
+    let mut io_error = None;
+    let tokens = Tokenizer::with_dialect(dialect).read(open(input)?).map_while(|token| match token {
+        Err(ReadError::Io(e)) => {
+            io_error = Some(e);
+            None
+        },
+        token => Some(token),
+    });
+    let result = Parser::new(tokens).parse_program();
+
+    if let Some(e) = io_error {
+        return Err(e.into());
+    }
+
+    result.map_err(|errors| Failure::Source(errors.into_iter().map(|e| e.into()).collect()))
+}
+
+fn resolve(program: &Program, word_size: WordSize) -> Result<Resolution, Failure> {
+    Resolver::new().with_externals(vm::library_names()).with_word_size(word_size).resolve(program)
+        .map_err(|errors| Failure::Source(errors.into_iter().map(|e| e.into()).collect()))
+}
+
+fn compile(input: &str, options: &Options) -> Result<Program, Failure> {
+    let mut program = parse(input, options.dialect)?;
+    let resolution = resolve(&program, options.word_size)?;
+
+    lowering::lower(&mut program, &resolution);
+
+    Ok(program)
+}
+
+fn run(input: &str, options: &Options, out: &mut dyn Write) -> Result<(), Failure> {
+    let program = compile(input, options)?;
+
+    Machine::with_word_size(&program, options.word_size)
+        .and_then(|machine| machine.with_output(out).run())
+        .map(|_| ())
+        .map_err(Failure::Runtime)
+}
+
+fn format(input: &str, options: &Options, out: &mut dyn Write) -> Result<(), Failure> {
+    let source = read_source(open(input)?)?;
+    let formatted = formatter::format(&source, &options.format)
+        .map_err(|e| Failure::Source(e.messages().into_iter().map(|message| message.into()).collect()))?;
+
+    if !options.check {
+        write!(out, "{}", formatted)?;
+    } else if formatted != source {
+        writeln!(out, "{}: needs formatting", input)?;
+        return Err(Failure::Unformatted);
+    }
+
+    Ok(())
+}
+
+/**
+ * Write the listing of the lowered program : the globals with their initial values, then the opcodes of each function
+ */
+fn print_program(program: &Program, out: &mut dyn Write) -> Result<(), Failure> {
+    for (name, variable) in &program.globals {
+        let size = variable.vector_size().map(|size| format!("[{}]", size)).unwrap_or_default();
+        let values: Vec<String> = variable.initial().iter().map(|value| format!(" {}", value)).collect();
+
+        writeln!(out, "{}{}{};", name, size, values.join(","))?;
+    }
+
+    for function in program.functions.values() {
+        let arguments: Vec<&str> = function.arguments.iter().map(|argument| argument.value.as_str()).collect();
+
+        writeln!(out, "\n{}({}):", function.name, arguments.join(", "))?;
+
+        for (index, opcode) in function.statements.iter().enumerate() {
+            writeln!(out, "{:>6}  {}", index, opcode)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn print_tokens(input: &str, dialect: Dialect, out: &mut dyn Write) -> Result<(), Failure> {
+    for token in Tokenizer::with_dialect(dialect).read(open(input)?) {
+        let token = token?;
+
+        writeln!(out, "{}\t{}", token.span, token.value)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use rsblang::formatter::{BraceStyle, FormatOptions, Indent};
+    use rsblang::layout::WordSize;
+    use rsblang::tokenizer::Dialect;
+
+    use crate::cli::{Command, Options, Status, UsageError};
+
+    fn parse(args: &[&str]) -> Result<Options, UsageError> {
+        Options::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn parse_arguments() {
+        assert_eq!(
+            Ok(Options { command: Command::Check, inputs: vec![String::from("a.b"), String::from("b.b")], output: None, format: FormatOptions::default(), check: false, word_size: WordSize::default(), dialect: Dialect::default() }),
+            parse(&["check", "a.b", "b.b"])
+        );
+        assert_eq!(
+            Ok(Options { command: Command::Build, inputs: vec![String::from("a.b")], output: Some(String::from("out")), format: FormatOptions::default(), check: false, word_size: WordSize::default(), dialect: Dialect::default() }),
+            parse(&["build", "-o", "out", "a.b"])
+        );
+        assert_eq!(
+            Ok(Options { command: Command::Tokens, inputs: vec![String::from("-")], output: None, format: FormatOptions::default(), check: false, word_size: WordSize::default(), dialect: Dialect::default() }),
+            parse(&["tokens", "-"])
+        );
+        assert_eq!(
+            Ok(Options {
+                command: Command::Format,
+                inputs: vec![String::from("a.b")],
+                output: None,
+                format: FormatOptions { indent: Indent::Tab, brace_style: BraceStyle::NextLine, dialect: Dialect::Historical },
+                check: true,
+                word_size: WordSize::default(),
+                dialect: Dialect::default(),
+            }),
+            parse(&["fmt", "--check", "--indent", "tab", "--brace", "next", "a.b"])
+        );
+        assert_eq!(Ok(Indent::Spaces(2)), parse(&["fmt", "--indent", "2", "a.b"]).map(|options| options.format.indent));
+        assert_eq!(Ok(WordSize::Bits16), parse(&["run", "--word", "16", "a.b"]).map(|options| options.word_size));
+        assert_eq!(
+            Ok((Dialect::Modern, Dialect::Modern)),
+            parse(&["fmt", "--dialect", "modern", "a.b"]).map(|options| (options.dialect, options.format.dialect))
+        );
+    }
+
+    #[test]
+    fn status_severity() {
+        assert_eq!(Status::Runtime, Status::SourceError.max(Status::Runtime));
+        assert_eq!(Status::Io, Status::Io.max(Status::SourceError));
+        assert_eq!(Status::SourceError, Status::Success.max(Status::SourceError));
+    }
+
+    #[test]
+    fn parse_arguments_errors() {
+        assert_eq!(Err(UsageError::Help), parse(&["run", "--help"]));
+        assert_eq!(Err(UsageError::Invalid(String::from("Missing command"))), parse(&[]));
+        assert_eq!(Err(UsageError::Invalid(String::from("Unknown command foo"))), parse(&["foo", "a.b"]));
+        assert_eq!(Err(UsageError::Invalid(String::from("Missing input file"))), parse(&["parse"]));
+        assert_eq!(Err(UsageError::Invalid(String::from("Missing file name after -o"))), parse(&["parse", "a.b", "-o"]));
+        assert_eq!(Err(UsageError::Invalid(String::from("Unknown option -x"))), parse(&["parse", "-x", "a.b"]));
+        assert_eq!(Err(UsageError::Invalid(String::from("Invalid indentation x"))), parse(&["fmt", "--indent", "x", "a.b"]));
+        assert_eq!(Err(UsageError::Invalid(String::from("Invalid brace style x"))), parse(&["fmt", "--brace", "x", "a.b"]));
+        assert_eq!(Err(UsageError::Invalid(String::from("Missing brace style after --brace"))), parse(&["fmt", "--brace"]));
+        assert_eq!(Err(UsageError::Invalid(String::from("Invalid word size 36"))), parse(&["run", "--word", "36", "a.b"]));
+        assert_eq!(Err(UsageError::Invalid(String::from("Invalid dialect c"))), parse(&["run", "--dialect", "c", "a.b"]));
+        assert_eq!(Err(UsageError::Invalid(String::from("Missing dialect after --dialect"))), parse(&["run", "a.b", "--dialect"]));
+    }
+}
@@ -0,0 +1,216 @@
+pub use options::{BraceStyle, FormatOptions, Indent};
+
+use crate::parser::parse_tokens;
+use crate::tokenizer::Tokenizer;
+use crate::Error;
+
+mod options;
+mod printer;
+
+/**
+ * Pretty-print the source code
+ * The comments and the spelling of the literals are kept, and at most one blank line
+ * is kept between the statements
+ */
+pub fn format(source: &str, options: &FormatOptions) -> Result<String, Error> {
+    let mut tokenizer = Tokenizer::with_dialect(options.dialect).with_trivia();
+
+    for c in source.chars() {
+        tokenizer.push(c)?;
+    }
+
+    tokenizer.finalize()?;
+
+    let tokens = tokenizer.into_lossless_tokens();
+    let program = parse_tokens(tokens.tokens.iter().map(|token| token.token.clone()))?;
+
+    Ok(printer::Printer::new(options, &tokens).print(&program))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::formatter::{format, BraceStyle, FormatOptions, Indent};
+    use crate::tokenizer::Dialect;
+
+    fn format_default(source: &str) -> String {
+        let formatted = format(source, &FormatOptions::default()).unwrap();
+
+        assert_eq!(formatted, format(&formatted, &FormatOptions::default()).unwrap(), "The formatting is not idempotent");
+
+        formatted
+    }
+
+    #[test]
+    fn format_program() {
+        let source = "printn(n,b) {\n\
+            extrn putchar;auto a;\n\
+            if(a=n/b)printn(a,b);\n\
+            putchar(n%b+'0');}\n\
+            main(){printn(017,8);}";
+
+        assert_eq!(
+            "printn(n, b) {\n    \
+                extrn putchar;\n    \
+                auto a;\n    \
+                if (a = n / b)\n        \
+                    printn(a, b);\n    \
+                putchar(n % b + '0');\n\
+            }\n\
+            \n\
+            main() {\n    \
+                printn(017, 8);\n\
+            }\n",
+            format_default(source)
+        );
+    }
+
+    #[test]
+    fn format_globals() {
+        assert_eq!(
+            "a;\nb 1, -2, 'x';\nv[010] \"s*n\";\n\nf() {\n}\n\nc;\n",
+            format_default("a;b 1,- 2,'x';v [ 010 ]\"s*n\";f(){}c;")
+        );
+    }
+
+    #[test]
+    fn format_statements() {
+        let source = "f(){while(1){if(x)goto l;else if(y)return;else{return(x);}\nswitch(x){case 1:l:x=+1;}}}";
+
+        assert_eq!(
+            "f() {\n    \
+                while (1) {\n        \
+                    if (x)\n            \
+                        goto l;\n        \
+                    else if (y)\n            \
+                        return;\n        \
+                    else {\n            \
+                        return (x);\n        \
+                    }\n        \
+                    switch (x) {\n            \
+                        case 1:\n            \
+                        l:\n            \
+                        x =+ 1;\n        \
+                    }\n    \
+                }\n\
+            }\n",
+            format_default(source)
+        );
+    }
+
+    #[test]
+    fn format_expressions() {
+        assert_eq!(
+            "f() {\n    \
+                a = (b = c) ? d : e ? f : g;\n    \
+                (a + b) * c - (d - e);\n    \
+                - -a;\n    \
+                -a++[1] + *b(2);\n\
+            }\n",
+            format_default("f(){a=(b=c)?d:(e?f:g);((a+b)*c)-(d-e);-(-a);-(a++)[1]+(*b(2));}")
+        );
+    }
+
+    #[test]
+    fn format_keeps_comments() {
+        let source = "/* header */\n\
+            f() { /* start */\n\
+            \n\
+            \n\
+            a; /* trailing */ b;\n\
+            /* before c */ c;\n\
+            /* end */ }\n\
+            /* footer */";
+
+        assert_eq!(
+            "/* header */\n\
+            f() { /* start */\n    \
+                a;\n    \
+                /* trailing */ b;\n    \
+                /* before c */ c;\n    \
+                /* end */\n\
+            }\n\
+            /* footer */\n",
+            format_default(source)
+        );
+    }
+
+    #[test]
+    fn format_keeps_comments_in_place() {
+        let source = "f(a /* first */, b) {\n\
+            g(a, /* x */\n b);\n\
+            x = /* c */ 1+/* d */2 ;\n\
+            h(v[/* i */ 1] /* last */) ;\n\
+            y = a ? /* then */ b : /* else */ c;\n\
+            auto /* v */ v 2, w /* end */;\n\
+            extrn /* e */ e;\n\
+            if (/* cond */ x /* after */) /* brace */ {\n\
+            return (x /* value */);\n\
+            } /* before else */ else\n\
+            a++ /* post */;\n\
+            }\n\
+            v 1, /* two */ 2 /* end */;";
+
+        assert_eq!(
+            "f(a /* first */, b) {\n    \
+                g(a, /* x */ b);\n    \
+                x = /* c */ 1 + /* d */ 2;\n    \
+                h(v[/* i */ 1] /* last */);\n    \
+                y = a ? /* then */ b : /* else */ c;\n    \
+                auto /* v */ v 2, w /* end */;\n    \
+                extrn /* e */ e;\n    \
+                if (/* cond */ x /* after */) /* brace */ {\n        \
+                    return (x /* value */);\n    \
+                } /* before else */ else\n        \
+                    a++ /* post */;\n\
+            }\n\
+            \n\
+            v 1, /* two */ 2 /* end */;\n",
+            format_default(source)
+        );
+    }
+
+    #[test]
+    fn format_comments_before_closing_tokens() {
+        assert_eq!("v[/* s */ 3] /* v */;\n", format_default("v[/* s */ 3]  /* v */;"));
+        assert_eq!("x /* only */;\n", format_default("x  /* only */;"));
+        assert_eq!(
+            "f() {\n    if (a) {\n        b; /* d */\n    }\n    c;\n    /* e */\n}\n",
+            format_default("f() { if (a) { b; /* d */ } c;\n/* e */ }")
+        );
+        assert_eq!("f() {\n    c = /* paren */ d;\n}\n", format_default("f() { c = ( /* paren */ d ); }"));
+        assert_eq!("f() {\n    c = /* p */ (a + b) * 2;\n}\n", format_default("f() { c = (/* p */ (a + b) * 2); }"));
+        assert_eq!(
+            "f(/* none */) {\n    f(/* none */);\n    f(/* x */ a);\n}\n",
+            format_default("f( /* none */ ) { f( /* none */); f(/* x */ a); }")
+        );
+    }
+
+    #[test]
+    fn format_options() {
+        let options = FormatOptions {
+            indent: Indent::Tab,
+            brace_style: BraceStyle::NextLine,
+            dialect: Dialect::Historical,
+        };
+
+        assert_eq!(
+            "f()\n{\n\tif (a)\n\t{\n\t\tb;\n\t}\n\telse\n\t{\n\t\tc;\n\t}\n}\n",
+            format("f(){if(a){b;}else{c;}}", &options).unwrap()
+        );
+        assert_eq!(
+            "f() {\n  if (a) {\n    b;\n  } else {\n    c;\n  }\n}\n",
+            format("f(){if(a){b;}else{c;}}", &FormatOptions { indent: Indent::Spaces(2), ..FormatOptions::default() }).unwrap()
+        );
+        assert_eq!("f() {\n    a =- 1;\n}\n", format("f(){a=-1;}", &FormatOptions::default()).unwrap());
+        assert_eq!(
+            "f() {\n    a = -1;\n}\n",
+            format("f(){a=-1;}", &FormatOptions { dialect: Dialect::Modern, ..FormatOptions::default() }).unwrap()
+        );
+    }
+
+    #[test]
+    fn format_errors() {
+        assert!(format("f() { a = ; }", &FormatOptions::default()).is_err());
+        assert!(format("f() { 'abc }", &FormatOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,62 @@
+use crate::tokenizer::Dialect;
+
+/**
+ * Indentation of the nested statements
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indent {
+    /**
+     * Indent using the given number of spaces
+     */
+    Spaces(usize),
+    Tab,
+}
+
+/**
+ * Placement of the opening brace of a block
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BraceStyle {
+    /**
+     * The brace is on the line of the statement : while (a) {
+     */
+    #[default]
+    SameLine,
+
+    /**
+     * The brace is on its own line, below the statement
+     */
+    NextLine,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatOptions {
+    pub indent: Indent,
+    pub brace_style: BraceStyle,
+    /**
+     * The dialect used to tokenize the source
+     */
+    pub dialect: Dialect,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            indent: Indent::Spaces(4),
+            brace_style: BraceStyle::SameLine,
+            dialect: Dialect::default(),
+        }
+    }
+}
+
+impl Indent {
+    /**
+     * Get the indentation string of one level
+     */
+    pub fn unit(&self) -> String {
+        match self {
+            Indent::Spaces(count) => " ".repeat(*count),
+            Indent::Tab => String::from("\t"),
+        }
+    }
+}
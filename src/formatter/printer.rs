@@ -0,0 +1,750 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::formatter::{BraceStyle, FormatOptions};
+use crate::parser::ast::{AutoVariable, Expression, SpannedExpression, SpannedStatement, Statement, UnaryOperator};
+use crate::parser::{Function, Program, Variable};
+use crate::tokenizer::{LosslessToken, LosslessTokens, Operator, Position, Span, Token, Trivia};
+
+/**
+ * Precedence levels of the expressions, from the lowest to the highest
+ */
+const ASSIGNMENT: u8 = 1;
+const CONDITIONAL: u8 = 2;
+const PREFIX: u8 = 10;
+const POSTFIX: u8 = 11;
+const ATOM: u8 = 12;
+
+/**
+ * A top-level definition
+ */
+enum Definition<'a> {
+    Global(&'a Variable),
+    Function(&'a Function),
+}
+
+/**
+ * A comment of the source, not printed yet
+ */
+struct Comment<'a> {
+    text: &'a str,
+    span: Span,
+    /**
+     * The comment is followed by a token on the same line, so it's printed just before this token
+     * Other comments are printed on their own line, or at the end of the line of the previous token
+     */
+    attached: bool,
+}
+
+/**
+ * Print the program in the canonical style
+ *
+ * The program gives the structure of the source, and the tokens give the comments,
+ * and the original spelling of the literals (like octal integers or escape sequences)
+ */
+pub(super) struct Printer<'a> {
+    options: &'a FormatOptions,
+    tokens: &'a [LosslessToken],
+    /**
+     * Source text of the literal tokens, indexed by their start offset
+     */
+    literals: HashMap<usize, &'a str>,
+    /**
+     * Comments not printed yet, in source order
+     */
+    comments: VecDeque<Comment<'a>>,
+    /**
+     * Comments printed at the start of the next line, before its text
+     */
+    leading: String,
+    indent: String,
+    depth: usize,
+    out: String,
+    /**
+     * Source line of the last printed element, to keep the blank lines of the source
+     * None at the start of a block, where no blank line is printed
+     */
+    last_line: Option<usize>,
+    /**
+     * Print a blank line before the next element, even if the source has none
+     */
+    force_blank_line: bool,
+    /**
+     * The next line continues the current one, like "else" after "}"
+     */
+    continue_line: bool,
+}
+
+impl<'a> Printer<'a> {
+    pub(super) fn new(options: &'a FormatOptions, source: &'a LosslessTokens) -> Printer<'a> {
+        let mut literals = HashMap::new();
+        let mut comments = VecDeque::new();
+
+        for token in &source.tokens {
+            if matches!(token.token.value, Token::Integer(_) | Token::Char(_) | Token::String(_)) {
+                literals.insert(token.token.span.start.offset, token.text.as_str());
+            }
+        }
+
+        for token in &source.tokens {
+            for trivia in &token.leading {
+                if let Trivia::Comment(text) = &trivia.value {
+                    // the comments ending a block are printed with its statements, not before the closing brace
+                    let attached = trivia.span.end.line == token.token.span.start.line && token.token.value != Token::ClosingBrace;
+
+                    comments.push_back(Comment { text: text.as_str(), span: trivia.span, attached });
+                }
+            }
+        }
+
+        for trivia in &source.trailing {
+            if let Trivia::Comment(text) = &trivia.value {
+                comments.push_back(Comment { text: text.as_str(), span: trivia.span, attached: false });
+            }
+        }
+
+        Printer {
+            options,
+            tokens: &source.tokens,
+            literals,
+            comments,
+            leading: String::new(),
+            indent: options.indent.unit(),
+            depth: 0,
+            out: String::new(),
+            last_line: None,
+            force_blank_line: false,
+            continue_line: false,
+        }
+    }
+
+    pub(super) fn print(mut self, program: &Program) -> String {
+        let mut definitions: Vec<(Span, Definition)> = program.globals.values()
+            .map(|variable| (variable.span(), Definition::Global(variable)))
+            .chain(program.functions.values().map(|function| (function.span, Definition::Function(function))))
+            .collect();
+
+        definitions.sort_by_key(|(span, _)| span.start.offset);
+
+        let mut previous_is_function = None;
+
+        for (span, definition) in definitions {
+            let is_function = matches!(definition, Definition::Function(_));
+
+            // functions are always separated from other definitions by a blank line
+            self.force_blank_line = previous_is_function.is_some_and(|previous| previous || is_function);
+            self.start_element(span.start);
+
+            match definition {
+                Definition::Global(variable) => self.global(variable),
+                Definition::Function(function) => self.function(function),
+            }
+
+            previous_is_function = Some(is_function);
+        }
+
+        // the remaining comments follow the last token
+        self.comments.iter_mut().for_each(|comment| comment.attached = false);
+        self.comments_before(usize::MAX);
+
+        self.out
+    }
+
+    fn global(&mut self, variable: &Variable) {
+        let span = variable.span();
+        let first = self.tokens.partition_point(|token| token.token.span.start.offset < span.start.offset);
+        let last = self.tokens.partition_point(|token| token.token.span.start.offset < span.end.offset);
+        let mut tokens = self.tokens[first..last].iter().peekable();
+
+        let mut text = variable.name().to_string();
+        tokens.next();
+
+        if let Some(token) = tokens.next_if(|token| token.token.value == Token::OpeningBracket) {
+            text.push_str(&self.suffix(token.token.span.start.offset));
+            text.push_str(&token.text);
+
+            for token in tokens.by_ref() {
+                if token.token.value == Token::ClosingBracket {
+                    text.push_str(&self.suffix(token.token.span.start.offset));
+                } else {
+                    text.push_str(&self.prefix(token.token.span.start.offset));
+                }
+
+                text.push_str(&token.text);
+
+                if token.token.value == Token::ClosingBracket {
+                    break;
+                }
+            }
+        }
+
+        let mut values = vec![String::new()];
+        let mut initialized = false;
+
+        for token in tokens {
+            let offset = token.token.span.start.offset;
+
+            match token.token.value {
+                Token::Operator(Operator::Comma) => {
+                    let comments = self.suffix(offset);
+
+                    values.last_mut().unwrap().push_str(&comments);
+                    values.push(String::new());
+                },
+                Token::EndOfStatement => {
+                    let comments = self.suffix(offset);
+
+                    values.last_mut().unwrap().push_str(&comments);
+                    break;
+                },
+                _ => {
+                    let comments = self.prefix(offset);
+                    let value = values.last_mut().unwrap();
+
+                    initialized = true;
+
+                    if !comments.is_empty() && !value.is_empty() {
+                        value.push(' ');
+                    }
+
+                    value.push_str(&comments);
+                    value.push_str(&token.text);
+                },
+            }
+        }
+
+        // without values, only the comments before the ";" are left, already preceded by a space
+        if initialized {
+            text.push(' ');
+        }
+
+        text.push_str(&values.join(", "));
+
+        text.push(';');
+        self.line(&text, span.end);
+    }
+
+    fn function(&mut self, function: &Function) {
+        let mut arguments: Vec<String> = Vec::with_capacity(function.arguments.len());
+
+        for (index, argument) in function.arguments.iter().enumerate() {
+            let mut text = format!("{}{}", self.prefix(argument.span.start.offset), argument.value);
+
+            if index + 1 < function.arguments.len() {
+                text.push_str(&self.suffix(self.token_after(argument.span.end.offset, Token::Operator(Operator::Comma))));
+            }
+
+            arguments.push(text);
+        }
+
+        let closing = self.token_before(function.body.span.start.offset);
+        let header = format!("{}({}{})", function.name, arguments.join(", "), self.closing(closing, arguments.is_empty()));
+
+        self.controlled(header, &function.body);
+    }
+
+    fn statement(&mut self, statement: &SpannedStatement) {
+        self.start_element(statement.span.start);
+
+        let end = statement.span.end;
+        // the location of the final ";" of the simple statements
+        let semicolon = end.offset.saturating_sub(1);
+
+        match &statement.value {
+            Statement::Auto(variables) => {
+                let variables: Vec<String> = variables.iter().map(|variable| self.auto_variable(variable)).collect();
+                let text = format!("auto {}{};", variables.join(", "), self.suffix(semicolon));
+
+                self.line(&text, end);
+            },
+            Statement::Extern(names) => {
+                let names: Vec<String> = names.iter().map(|name| format!("{}{}", self.prefix(name.span.start.offset), name.value)).collect();
+                let text = format!("extrn {}{};", names.join(", "), self.suffix(semicolon));
+
+                self.line(&text, end);
+            },
+            Statement::Block(statements) => {
+                self.line("{", self.token_end_after(statement.span.start.offset));
+                self.block_contents(statements, statement.span);
+            },
+            Statement::If { condition, then, otherwise } => self.if_statement("", statement.span, condition, then, otherwise.as_deref()),
+            Statement::While { condition, body } => {
+                let header = format!("while {}", self.condition(statement.span, condition, body));
+
+                self.controlled(header, body);
+            },
+            Statement::Switch { value, body } => {
+                let header = format!("switch {}", self.condition(statement.span, value, body));
+
+                self.controlled(header, body);
+            },
+            Statement::Case { value, statement } => {
+                let colon = self.token_end_after(value.span.end.offset);
+                let text = format!("case {}{}:", self.expression(value), self.suffix(colon.offset - 1));
+
+                self.line(&text, colon);
+                self.statement(statement);
+            },
+            Statement::Label { name, statement } => {
+                let colon = self.token_end_after(name.span.end.offset);
+                let text = format!("{}{}:", name.value, self.suffix(colon.offset - 1));
+
+                self.line(&text, colon);
+                self.statement(statement);
+            },
+            Statement::Goto(target) => {
+                let text = format!("goto {}{};", self.expression(target), self.suffix(semicolon));
+
+                self.line(&text, end);
+            },
+            Statement::Return(None) => {
+                let text = format!("return{};", self.suffix(semicolon));
+
+                self.line(&text, end);
+            },
+            Statement::Return(Some(value)) => {
+                let text = format!("return {}{};", self.parenthesized(value, semicolon), self.suffix(semicolon));
+
+                self.line(&text, end);
+            },
+            Statement::Expression(expression) => {
+                let text = format!("{}{};", self.expression(expression), self.suffix(semicolon));
+
+                self.line(&text, end);
+            },
+            Statement::Null => self.line(";", end),
+        }
+    }
+
+    fn auto_variable(&mut self, variable: &AutoVariable) -> String {
+        let comments = self.prefix(variable.name.span.start.offset);
+
+        match variable.size {
+            Some(size) => format!("{}{} {}", comments, variable.name.value, size),
+            None => format!("{}{}", comments, variable.name.value),
+        }
+    }
+
+    /**
+     * Print the condition of a control statement, with its parenthesis
+     */
+    fn condition(&mut self, span: Span, condition: &SpannedExpression, body: &SpannedStatement) -> String {
+        let opening = self.token_after(span.start.offset, Token::OpeningParenthesis);
+
+        format!("{}{}", self.prefix(opening), self.parenthesized(condition, body.span.start.offset))
+    }
+
+    /**
+     * Print the expression between parenthesis, where the closing one is the last token before the given offset
+     */
+    fn parenthesized(&mut self, expression: &SpannedExpression, end: usize) -> String {
+        let closing = self.token_before(end);
+        let text = self.expression(expression);
+
+        format!("({}{})", text, self.suffix(closing))
+    }
+
+    fn if_statement(&mut self, prefix: &str, span: Span, condition: &SpannedExpression, then: &SpannedStatement, otherwise: Option<&SpannedStatement>) {
+        let header = format!("{}if {}", prefix, self.condition(span, condition, then));
+
+        self.controlled(header, then);
+
+        let Some(otherwise) = otherwise else {
+            return;
+        };
+
+        // "} else {" when the brace of the then block is not followed by a comment
+        if self.options.brace_style == BraceStyle::SameLine && matches!(then.value, Statement::Block(_)) && self.out.ends_with("}\n") {
+            self.out.pop();
+            self.continue_line = true;
+        }
+
+        let comments = self.prefix(self.token_before(otherwise.span.start.offset));
+
+        match &otherwise.value {
+            Statement::If { condition, then, otherwise: next } => {
+                self.if_statement(&format!("{}else ", comments), otherwise.span, condition, then, next.as_deref())
+            },
+            _ => self.controlled(format!("{}else", comments), otherwise),
+        }
+    }
+
+    /**
+     * Print a statement with a body, like while or a function definition
+     */
+    fn controlled(&mut self, header: String, body: &SpannedStatement) {
+        let Statement::Block(statements) = &body.value else {
+            self.line(&header, self.end_before(body.span.start.offset));
+            self.depth += 1;
+            self.statement(body);
+            self.depth -= 1;
+
+            return;
+        };
+
+        let brace_end = self.token_end_after(body.span.start.offset);
+
+        match self.options.brace_style {
+            BraceStyle::SameLine => {
+                let text = format!("{} {}{{", header, self.prefix(body.span.start.offset));
+
+                self.line(&text, brace_end);
+            },
+            BraceStyle::NextLine => {
+                self.line(&header, self.end_before(body.span.start.offset));
+                self.comments_before(body.span.start.offset);
+                self.leading = self.prefix(body.span.start.offset);
+                self.line("{", brace_end);
+            },
+        }
+
+        self.block_contents(statements, body.span);
+    }
+
+    /**
+     * Print the statements of a block, and its closing brace
+     */
+    fn block_contents(&mut self, statements: &[SpannedStatement], span: Span) {
+        self.depth += 1;
+        self.last_line = None;
+
+        for statement in statements {
+            self.statement(statement);
+        }
+
+        // comments at the end of the block, before the closing brace
+        self.comments_before(span.end.offset - 1);
+        self.depth -= 1;
+        self.leading = self.prefix(span.end.offset - 1);
+        self.line("}", span.end);
+    }
+
+    /**
+     * Print the expression, with the comments located before its tokens
+     */
+    fn expression(&mut self, expression: &SpannedExpression) -> String {
+        let span = expression.span;
+        // the parenthesis of the source are not printed, so the comments following them are printed before the expression
+        let comments = self.prefix(self.skip_parenthesis(span.start.offset));
+        let text = self.expression_text(expression);
+
+        format!("{}{}", comments, text)
+    }
+
+    fn expression_text(&mut self, expression: &SpannedExpression) -> String {
+        let span = expression.span;
+
+        match &expression.value {
+            Expression::Integer(value) => self.literal(span).unwrap_or_else(|| value.to_string()),
+            Expression::Char(chars) => self.literal(span).unwrap_or_else(|| Token::Char(*chars).to_string()),
+            Expression::String(value) => self.literal(span).unwrap_or_else(|| Token::String(value.clone()).to_string()),
+            Expression::Name { name, .. } => name.to_string(),
+            Expression::Assign { operator, target, value } => {
+                let target_text = self.operand(target, CONDITIONAL);
+                let comments = self.prefix(self.operator_after(target.span.end.offset));
+                let value = self.operand(value, ASSIGNMENT);
+
+                format!("{} {}={} {}", target_text, comments, operator.map(|operator| operator.symbol()).unwrap_or_default(), value)
+            },
+            Expression::Unary { operator, operand } => {
+                let symbol = match operator {
+                    UnaryOperator::Negate => "-",
+                    UnaryOperator::Not => "!",
+                    UnaryOperator::Address => "&",
+                    UnaryOperator::Indirection => "*",
+                    UnaryOperator::PreIncrement | UnaryOperator::PostIncrement => "++",
+                    UnaryOperator::PreDecrement | UnaryOperator::PostDecrement => "--",
+                };
+
+                if matches!(operator, UnaryOperator::PostIncrement | UnaryOperator::PostDecrement) {
+                    let text = self.operand(operand, POSTFIX);
+
+                    return format!("{}{}{}", text, self.suffix(self.operator_after(operand.span.end.offset)), symbol);
+                }
+
+                let operand = self.operand(operand, PREFIX);
+
+                // "- -a" must not be printed as the decrement "--a"
+                if operand.starts_with(&symbol[..1]) && (symbol.starts_with('-') || symbol.starts_with('+')) {
+                    format!("{} {}", symbol, operand)
+                } else {
+                    format!("{}{}", symbol, operand)
+                }
+            },
+            Expression::Binary { operator, left, right } => {
+                let precedence = Self::precedence(&expression.value);
+
+                // binary operators are left associative
+                let left_text = self.operand(left, precedence);
+                let comments = self.prefix(self.operator_after(left.span.end.offset));
+                let right = self.operand(right, precedence + 1);
+
+                format!("{} {}{} {}", left_text, comments, operator.symbol(), right)
+            },
+            Expression::Conditional { condition, then, otherwise } => {
+                let condition_text = self.operand(condition, CONDITIONAL + 1);
+                let question = self.prefix(self.token_after(condition.span.end.offset, Token::Operator(Operator::Question)));
+                let then_text = self.operand(then, ASSIGNMENT);
+                let colon = self.prefix(self.token_after(then.span.end.offset, Token::Operator(Operator::Colon)));
+                let otherwise = self.operand(otherwise, CONDITIONAL);
+
+                format!("{} {}? {} {}: {}", condition_text, question, then_text, colon, otherwise)
+            },
+            Expression::Index { vector, index } => {
+                let vector_text = self.operand(vector, POSTFIX);
+                let opening = self.suffix(self.token_after(vector.span.end.offset, Token::OpeningBracket));
+                let index = self.expression(index);
+
+                format!("{}{}[{}{}]", vector_text, opening, index, self.suffix(span.end.offset - 1))
+            },
+            Expression::Call { function, arguments: values } => {
+                let function_text = self.operand(function, POSTFIX);
+                let opening = self.suffix(self.token_after(function.span.end.offset, Token::OpeningParenthesis));
+                let mut arguments: Vec<String> = Vec::with_capacity(values.len());
+
+                for (index, argument) in values.iter().enumerate() {
+                    let mut text = self.expression(argument);
+
+                    if index + 1 < values.len() {
+                        text.push_str(&self.suffix(self.token_after(argument.span.end.offset, Token::Operator(Operator::Comma))));
+                    }
+
+                    arguments.push(text);
+                }
+
+                let closing = self.closing(span.end.offset - 1, arguments.is_empty());
+
+                format!("{}{}({}{})", function_text, opening, arguments.join(", "), closing)
+            },
+        }
+    }
+
+    /**
+     * Print a sub expression, with parenthesis if its precedence is lower than the required one
+     */
+    fn operand(&mut self, expression: &SpannedExpression, precedence: u8) -> String {
+        if Self::precedence(&expression.value) < precedence {
+            format!("({})", self.expression(expression))
+        } else {
+            self.expression(expression)
+        }
+    }
+
+    fn precedence(expression: &Expression) -> u8 {
+        use crate::parser::ast::BinaryOperator::*;
+
+        match expression {
+            Expression::Assign { .. } => ASSIGNMENT,
+            Expression::Conditional { .. } => CONDITIONAL,
+            Expression::Binary { operator, .. } => match operator {
+                Or => 3,
+                And => 4,
+                Equal | NotEqual => 5,
+                Less | LessEqual | Greater | GreaterEqual => 6,
+                ShiftLeft | ShiftRight => 7,
+                Add | Subtract => 8,
+                Modulo | Multiply | Divide => 9,
+            },
+            Expression::Unary { operator: UnaryOperator::PostIncrement | UnaryOperator::PostDecrement, .. } => POSTFIX,
+            Expression::Unary { .. } => PREFIX,
+            Expression::Index { .. } | Expression::Call { .. } => POSTFIX,
+            Expression::Integer(_) | Expression::Char(_) | Expression::String(_) | Expression::Name { .. } => ATOM,
+        }
+    }
+
+    /**
+     * Get the source text of a literal
+     * Returns None if the span is not exactly a literal token, like a parenthesized literal
+     */
+    fn literal(&self, span: Span) -> Option<String> {
+        self.literals.get(&span.start.offset)
+            .filter(|text| text.len() == span.len())
+            .map(|text| text.to_string())
+    }
+
+    /**
+     * Print the comments located before the given offset, each on its own line
+     * Stops at the first comment attached to the following token, which is printed with this token
+     */
+    fn comments_before(&mut self, offset: usize) {
+        while self.comments.front().is_some_and(|comment| comment.span.start.offset < offset && !comment.attached) {
+            let comment = self.comments.pop_front().unwrap();
+
+            self.blank_line(comment.span.start.line);
+            self.line(comment.text, comment.span.end);
+        }
+    }
+
+    /**
+     * Prepare the printing of a statement or a definition starting at the given position :
+     * print the comments of the previous lines, keep the blank lines, and take the comments
+     * printed before its first token
+     */
+    fn start_element(&mut self, start: Position) {
+        self.comments_before(start.offset);
+
+        let line = self.comments.front()
+            .filter(|comment| comment.span.start.offset < start.offset)
+            .map_or(start.line, |comment| comment.span.start.line);
+
+        self.blank_line(line);
+        self.leading = self.prefix(start.offset);
+    }
+
+    /**
+     * Take the comments located before the given offset
+     */
+    fn take_comments(&mut self, offset: usize) -> Vec<&'a str> {
+        let mut comments = Vec::new();
+
+        while self.comments.front().is_some_and(|comment| comment.span.start.offset < offset) {
+            comments.push(self.comments.pop_front().unwrap().text);
+        }
+
+        comments
+    }
+
+    /**
+     * Print the comments located before the token at the given offset, each followed by a space
+     */
+    fn prefix(&mut self, offset: usize) -> String {
+        self.take_comments(offset).iter().map(|comment| format!("{} ", comment)).collect()
+    }
+
+    /**
+     * Print the comments located before the token at the given offset, each preceded by a space
+     * Used before the closing tokens, which are printed without space
+     */
+    fn suffix(&mut self, offset: usize) -> String {
+        self.take_comments(offset).iter().map(|comment| format!(" {}", comment)).collect()
+    }
+
+    /**
+     * Print the comments located before a closing token at the given offset
+     * They are separated by a space from the enclosed content, but not from the opening token when there is no content
+     */
+    fn closing(&mut self, offset: usize, empty: bool) -> String {
+        let comments = self.suffix(offset);
+
+        if empty {
+            comments.trim_start().to_string()
+        } else {
+            comments
+        }
+    }
+
+    /**
+     * Keep a blank line of the source before an element starting at the given line
+     */
+    fn blank_line(&mut self, line: usize) {
+        if let Some(last_line) = self.last_line {
+            if self.force_blank_line || line > last_line + 1 {
+                self.out.push('\n');
+            }
+        }
+
+        self.force_blank_line = false;
+    }
+
+    /**
+     * Print a line for an element ending at the given source position
+     * The comments following the element on the same source line are kept at the end of the line
+     */
+    fn line(&mut self, text: &str, end: Position) {
+        if self.continue_line {
+            self.out.push(' ');
+            self.continue_line = false;
+        } else {
+            for _ in 0..self.depth {
+                self.out.push_str(&self.indent);
+            }
+        }
+
+        self.out.push_str(&std::mem::take(&mut self.leading));
+        self.out.push_str(text);
+
+        let next_token = self.tokens.get(self.tokens.partition_point(|token| token.token.span.start.offset < end.offset))
+            .map(|token| token.token.span.start.offset)
+            .unwrap_or(usize::MAX);
+        let mut last_line = end.line;
+
+        while let Some(comment) = self.comments.front() {
+            if comment.span.start.line != end.line || comment.span.start.offset >= next_token || comment.attached {
+                break;
+            }
+
+            self.out.push(' ');
+            self.out.push_str(comment.text);
+            last_line = comment.span.end.line;
+            self.comments.pop_front();
+        }
+
+        self.out.push('\n');
+        self.last_line = Some(last_line);
+    }
+
+    /**
+     * Get the end of the first token starting at or after the given offset
+     */
+    fn token_end_after(&self, offset: usize) -> Position {
+        let index = self.tokens.partition_point(|token| token.token.span.start.offset < offset);
+
+        self.tokens.get(index)
+            .or(self.tokens.last())
+            .map(|token| token.token.span.end)
+            .unwrap_or_default()
+    }
+
+    /**
+     * Get the start offset of the first token equal to the given one, starting at or after the given offset
+     */
+    fn token_after(&self, offset: usize, token: Token) -> usize {
+        let index = self.tokens.partition_point(|other| other.token.span.start.offset < offset);
+
+        self.tokens[index..].iter()
+            .find(|other| other.token.value == token)
+            .map_or(offset, |other| other.token.span.start.offset)
+    }
+
+    /**
+     * Get the start offset of the first token starting at or after the given offset, which is not an opening parenthesis
+     */
+    fn skip_parenthesis(&self, offset: usize) -> usize {
+        let index = self.tokens.partition_point(|token| token.token.span.start.offset < offset);
+
+        self.tokens[index..].iter()
+            .find(|token| token.token.value != Token::OpeningParenthesis)
+            .map_or(offset, |token| token.token.span.start.offset)
+    }
+
+    /**
+     * Get the start offset of the first operator starting at or after the given offset
+     */
+    fn operator_after(&self, offset: usize) -> usize {
+        let index = self.tokens.partition_point(|token| token.token.span.start.offset < offset);
+
+        self.tokens[index..].iter()
+            .find(|token| matches!(token.token.value, Token::Operator(_)))
+            .map_or(offset, |token| token.token.span.start.offset)
+    }
+
+    /**
+     * Get the start offset of the last token starting before the given offset
+     */
+    fn token_before(&self, offset: usize) -> usize {
+        let index = self.tokens.partition_point(|token| token.token.span.start.offset < offset);
+
+        index.checked_sub(1)
+            .map_or(offset, |index| self.tokens[index].token.span.start.offset)
+    }
+
+    /**
+     * Get the end of the last token starting before the given offset
+     */
+    fn end_before(&self, offset: usize) -> Position {
+        let index = self.tokens.partition_point(|token| token.token.span.start.offset < offset);
+
+        index.checked_sub(1)
+            .map(|index| self.tokens[index].token.span.end)
+            .unwrap_or_default()
+    }
+}
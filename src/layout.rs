@@ -0,0 +1,206 @@
+use std::fmt::{Display, Formatter};
+
+/**
+ * The end of file character "*e", which also terminates the strings
+ */
+pub const EOT: i64 = 4;
+
+/**
+ * Maximum number of addressable words, whatever the word size
+ */
+const MAX_WORDS: usize = 1 << 24;
+
+/**
+ * Size of the machine word
+ *
+ * All values are words : integers, characters and addresses. Memory is addressed by words,
+ * and the address 0 is the null pointer.
+ *
+ * Characters are bytes, the characters of the source being encoded in UTF-8.
+ * A char constant is right-justified into its word, so 'ab' is ('a' << 8) | 'b'.
+ * A string is packed into consecutive words, the first character of a word into its lowest byte,
+ * and is terminated by "*e".
+ * A vector is a word holding the address of its elements.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WordSize {
+    /**
+     * The word of the PDP-11, holding 2 characters
+     */
+    Bits16,
+    Bits32,
+    #[default]
+    Bits64,
+}
+
+impl WordSize {
+    pub fn from_bits(bits: u32) -> Option<WordSize> {
+        match bits {
+            16 => Some(WordSize::Bits16),
+            32 => Some(WordSize::Bits32),
+            64 => Some(WordSize::Bits64),
+            _ => None,
+        }
+    }
+
+    pub fn bits(&self) -> u32 {
+        match self {
+            WordSize::Bits16 => 16,
+            WordSize::Bits32 => 32,
+            WordSize::Bits64 => 64,
+        }
+    }
+
+    /**
+     * Get the number of characters packed into a word
+     */
+    pub fn chars_per_word(&self) -> usize {
+        self.bits() as usize / 8
+    }
+
+    /**
+     * Get the number of addressable words
+     * Addresses are positive words, so 16 bits words can address 32K words
+     */
+    pub fn address_limit(&self) -> usize {
+        MAX_WORDS.min(1 << (self.bits() - 1))
+    }
+
+    /**
+     * Truncate a value to the word size, keeping its sign
+     */
+    pub fn wrap(&self, value: i64) -> i64 {
+        match self {
+            WordSize::Bits16 => value as i16 as i64,
+            WordSize::Bits32 => value as i32 as i64,
+            WordSize::Bits64 => value,
+        }
+    }
+
+    /**
+     * Get the word as an unsigned value, for example to display it in octal
+     */
+    pub fn unsigned(&self, value: i64) -> u64 {
+        match self {
+            WordSize::Bits16 => value as u16 as u64,
+            WordSize::Bits32 => value as u32 as u64,
+            WordSize::Bits64 => value as u64,
+        }
+    }
+
+    /**
+     * Get the characters of a word, from the highest byte, ignoring the null bytes
+     */
+    pub fn unpack_chars(&self, word: i64) -> Vec<u8> {
+        self.unsigned(word).to_be_bytes()[8 - self.chars_per_word()..].iter()
+            .copied()
+            .filter(|byte| *byte != 0)
+            .collect()
+    }
+
+    /**
+     * Check that the bytes of a char constant fit into a word
+     */
+    pub fn fits_chars(&self, chars: &[char; 4]) -> bool {
+        char_bytes(chars).len() <= self.chars_per_word()
+    }
+
+    /**
+     * Pack a string into words, followed by the terminator
+     */
+    pub fn pack_string(&self, string: &str) -> Vec<i64> {
+        let bytes: Vec<u8> = string.bytes().chain([EOT as u8]).collect();
+
+        bytes.chunks(self.chars_per_word())
+            .map(|chunk| chunk.iter().rev().fold(0, |word, byte| (word << 8) | *byte as i64))
+            .map(|word| self.wrap(word))
+            .collect()
+    }
+
+    /**
+     * Get the character at the given index of a string, as the word containing it,
+     * and the shift of the character into this word
+     */
+    pub fn char_location(&self, index: usize) -> (usize, u32) {
+        (index / self.chars_per_word(), (index % self.chars_per_word()) as u32 * 8)
+    }
+}
+
+impl Display for WordSize {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} bits", self.bits())
+    }
+}
+
+/**
+ * Pack the bytes of a char constant into a word, the last byte in the lowest byte
+ * The constant must fit into the word, see WordSize::fits_chars()
+ */
+pub fn pack_chars(chars: &[char; 4]) -> i64 {
+    char_bytes(chars).iter().fold(0, |word, byte| (word << 8) | *byte as i64)
+}
+
+/**
+ * Get the bytes of a char constant : its characters encoded in UTF-8, like the strings
+ * The trailing null characters only fill the constant, they are not part of it
+ */
+fn char_bytes(chars: &[char; 4]) -> Vec<u8> {
+    let size = chars.iter().rposition(|c| *c != '\0').map(|last| last + 1).unwrap_or(0);
+
+    chars[..size].iter().collect::<String>().into_bytes()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::layout::{pack_chars, WordSize};
+
+    #[test]
+    fn pack_char_constants() {
+        assert_eq!(0, pack_chars(&['\0'; 4]));
+        assert_eq!(97, pack_chars(&['a', '\0', '\0', '\0']));
+        assert_eq!(0x61626364, pack_chars(&['a', 'b', 'c', 'd']));
+        assert_eq!(0xC3A9, pack_chars(&['é', '\0', '\0', '\0']));
+        assert_eq!("é".bytes().collect::<Vec<_>>(), WordSize::Bits16.unpack_chars(pack_chars(&['é', '\0', '\0', '\0'])));
+    }
+
+    #[test]
+    fn fit_char_constants() {
+        assert!(WordSize::Bits16.fits_chars(&['a', 'b', '\0', '\0']));
+        assert!(WordSize::Bits16.fits_chars(&['é', '\0', '\0', '\0']));
+        assert!(!WordSize::Bits16.fits_chars(&['a', 'b', 'c', '\0']));
+        assert!(!WordSize::Bits16.fits_chars(&['é', 'a', '\0', '\0']));
+        assert!(WordSize::Bits32.fits_chars(&['a', 'b', 'c', 'd']));
+        assert!(!WordSize::Bits64.fits_chars(&['€', '€', '€', '\0']));
+    }
+
+    #[test]
+    fn wrap_words() {
+        assert_eq!(-32768, WordSize::Bits16.wrap(32768));
+        assert_eq!(0, WordSize::Bits16.wrap(65536));
+        assert_eq!(i32::MIN as i64, WordSize::Bits32.wrap(i32::MAX as i64 + 1));
+        assert_eq!(1 << 40, WordSize::Bits64.wrap(1 << 40));
+        assert_eq!(0o177777, WordSize::Bits16.unsigned(-1));
+    }
+
+    #[test]
+    fn pack_strings() {
+        assert_eq!(vec![0x6261, 0x0463], WordSize::Bits16.pack_string("abc"));
+        assert_eq!(vec![0x04636261], WordSize::Bits32.pack_string("abc"));
+        assert_eq!(vec![0x6463_6261, 0x04], WordSize::Bits32.pack_string("abcd"));
+    }
+
+    #[test]
+    fn unpack_chars() {
+        assert_eq!(b"ab".to_vec(), WordSize::Bits16.unpack_chars(0x6162));
+        assert_eq!(b"ok".to_vec(), WordSize::Bits64.unpack_chars(pack_chars(&['o', 'k', '\0', '\0'])));
+        assert_eq!(vec![0xFF, 0xFF], WordSize::Bits16.unpack_chars(-1));
+    }
+
+    #[test]
+    fn address_limits() {
+        assert_eq!(32768, WordSize::Bits16.address_limit());
+        assert_eq!(1 << 24, WordSize::Bits32.address_limit());
+        assert_eq!(Some(WordSize::Bits32), WordSize::from_bits(32));
+        assert_eq!(None, WordSize::from_bits(36));
+    }
+}
@@ -0,0 +1,152 @@
+//! Compiler for the B language
+//!
+//! The compilation is done in multiple passes :
+//! - the [tokenizer] splits the source code into [tokens](tokenizer::Token)
+//! - the [parser] builds the [program](parser::Program) from the tokens
+//! - the [resolver] binds the names used by the functions to their declarations
+//! - the [lowering] translates the functions into [opcodes](parser::Opcode)
+//! - the [vm] executes the lowered program, using the memory [layout]
+//!
+//! The [formatter] pretty-prints the source code.
+//!
+//! The functions of this module run all the passes from the source code :
+//!
+//! ```
+//! let program = rsblang::parse("main() { extrn putchar; putchar('ok*n'); }").unwrap();
+//!
+//! assert!(program.functions.contains_key("main"));
+//! ```
+
+use std::fmt::{Display, Formatter};
+
+use crate::parser::{ParseError, Parser, Program};
+use crate::resolver::{Resolution, ResolveError, Resolver};
+use crate::vm::{Machine, RuntimeError};
+use crate::tokenizer::{Spanned, Token, TokenError, Tokenizer};
+
+pub mod formatter;
+pub mod layout;
+pub mod lowering;
+pub mod parser;
+pub mod resolver;
+pub mod tokenizer;
+pub mod vm;
+
+/**
+ * Error raised by one of the compilation passes
+ */
+#[derive(Debug, Clone)]
+pub enum Error {
+    /**
+     * The source contains an invalid token
+     */
+    Token(TokenError),
+
+    /**
+     * The source contains syntax errors
+     */
+    Parse(Vec<ParseError>),
+
+    /**
+     * The functions use undeclared names
+     */
+    Resolve(Vec<ResolveError>),
+
+    /**
+     * The execution of the program failed
+     */
+    Runtime(RuntimeError),
+}
+
+impl Error {
+    /**
+     * Get all errors as displayable messages
+     */
+    pub fn messages(&self) -> Vec<String> {
+        match self {
+            Error::Token(e) => vec![e.to_string()],
+            Error::Parse(errors) => errors.iter().map(|e| e.to_string()).collect(),
+            Error::Resolve(errors) => errors.iter().map(|e| e.to_string()).collect(),
+            Error::Runtime(e) => vec![e.to_string()],
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.messages().join("\n"))
+    }
+}
+
+impl std::error::Error for Error {
+
+}
+
+impl From<TokenError> for Error {
+    fn from(e: TokenError) -> Self {
+        Error::Token(e)
+    }
+}
+
+impl From<Vec<ParseError>> for Error {
+    fn from(errors: Vec<ParseError>) -> Self {
+        Error::Parse(errors)
+    }
+}
+
+impl From<Vec<ResolveError>> for Error {
+    fn from(errors: Vec<ResolveError>) -> Self {
+        Error::Resolve(errors)
+    }
+}
+
+impl From<RuntimeError> for Error {
+    fn from(e: RuntimeError) -> Self {
+        Error::Runtime(e)
+    }
+}
+
+/**
+ * Split the source code into tokens
+ */
+pub fn tokenize(source: &str) -> Result<Vec<Spanned<Token>>, TokenError> {
+    Ok(Tokenizer::from_string(source)?.into_tokens())
+}
+
+/**
+ * Parse the source code into a program
+ * The source is tokenized while parsing, so an invalid token is reported as a parse error
+ */
+pub fn parse(source: &str) -> Result<Program, Error> {
+    Ok(Parser::new(Tokenizer::new().stream(source.chars())).parse_program()?)
+}
+
+/**
+ * Parse the source code, and bind the names used by the functions to their declarations
+ * The functions of the runtime library can be declared by extrn
+ */
+pub fn resolve(source: &str) -> Result<(Program, Resolution), Error> {
+    let program = parse(source)?;
+    let resolution = Resolver::new().with_externals(vm::library_names()).resolve(&program)?;
+
+    Ok((program, resolution))
+}
+
+/**
+ * Parse and resolve the source code, and translate the functions into opcodes
+ */
+pub fn compile(source: &str) -> Result<Program, Error> {
+    let (mut program, resolution) = resolve(source)?;
+
+    lowering::lower(&mut program, &resolution);
+
+    Ok(program)
+}
+
+/**
+ * Compile the source code, and execute its main function
+ * Returns the value returned by main
+ */
+pub fn run(source: &str) -> Result<i64, Error> {
+    Ok(Machine::new(&compile(source)?)?.run()?)
+}
@@ -0,0 +1,106 @@
+pub use lowering::{lower, lower_function};
+
+#[allow(clippy::module_inception)]
+mod lowering;
+
+#[cfg(test)]
+mod test {
+    use crate::parser::ast::BinaryOperator;
+    use crate::parser::Opcode;
+    use crate::tokenizer::Symbol;
+
+    use Opcode::*;
+
+    fn code(source: &str) -> Vec<Opcode> {
+        crate::compile(source).unwrap().functions["f"].statements.clone()
+    }
+
+    #[test]
+    fn lower_printn() {
+        let program = crate::compile("printn(n, b) { extrn putchar; auto a; if (a = n / b) printn(a, b); putchar(n % b + '0'); }").unwrap();
+
+        assert_eq!(
+            vec![
+                Enter(3),
+                Local(2), Local(0), Load, Local(1), Load, Binary(BinaryOperator::Divide), Store,
+                JumpIfZero(17),
+                Global(Symbol::intern("printn")), Load, Local(2), Load, Local(1), Load, Call(2), Pop,
+                Global(Symbol::intern("putchar")), Load,
+                Local(0), Load, Local(1), Load, Binary(BinaryOperator::Modulo), Constant(48), Binary(BinaryOperator::Add),
+                Call(1), Pop,
+                Constant(0), Return,
+            ],
+            program.functions["printn"].statements
+        );
+    }
+
+    #[test]
+    fn lower_lvalues() {
+        assert_eq!(
+            vec![
+                Enter(3), Vector { local: 2, size: 10 },
+                Local(2), Load, Local(1), Load, Load, Binary(BinaryOperator::Add), Local(0), Store, Pop,
+                Local(1), Load, Duplicate, Load, Constant(2), Binary(BinaryOperator::Multiply), Store, Pop,
+                Local(0), PostIncrement(-1), Pop,
+                Local(2), Load, Constant(1), Binary(BinaryOperator::Add), Increment(1), Pop,
+                Local(0), Return,
+                Constant(0), Return,
+            ],
+            code("f(a, p) { auto v 10; v[*p] = &a; *p =* 2; a--; ++v[1]; return (&a); }")
+        );
+    }
+
+    #[test]
+    fn lower_control_flow() {
+        assert_eq!(
+            vec![
+                Enter(1),
+                Local(0), Load, JumpIfZero(12),
+                Local(0), Load, Constant(1), Binary(BinaryOperator::Greater), JumpIfZero(10),
+                Jump(11),
+                Jump(9),
+                Jump(1),
+                Constant(0), Return,
+            ],
+            code("f(a) { while (a) if (a > 1) continue: ; else goto continue; }")
+        );
+        assert_eq!(
+            vec![
+                Enter(1),
+                Local(0), Load, JumpIfZero(6), Constant(1), Jump(7), Constant(2), Pop,
+                Constant(0), Return,
+            ],
+            code("f(a) { a ? 1 : 2; }")
+        );
+    }
+
+    #[test]
+    fn lower_switch() {
+        assert_eq!(
+            vec![
+                Enter(1),
+                Local(0), Load, Jump(9),
+                Constant(1), Return,
+                Constant(2), Return,
+                Jump(12),
+                Case(1, 4), Case(24930, 6), Pop,
+                Constant(0), Return,
+            ],
+            code("f(a) { switch (a) { case 1: return (1); case 'ab': return (2); } }")
+        );
+    }
+
+    #[test]
+    fn lower_labels() {
+        assert_eq!(
+            vec![
+                Enter(1),
+                Jump(6),
+                Local(0), Constant(6), Store, Pop,
+                Local(0), Load, JumpIndirect,
+                Constant(0), Return,
+            ],
+            code("f() { auto l; goto end; l = end; end: goto l; }")
+        );
+    }
+}
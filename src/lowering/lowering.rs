@@ -0,0 +1,322 @@
+use crate::parser::ast::{BinaryOperator, Expression, NodeId, SpannedExpression, SpannedStatement, Statement, UnaryOperator};
+use crate::layout::pack_chars;
+use crate::parser::{Function, Opcode, Program};
+use crate::resolver::{Binding, FunctionScope, Resolution};
+
+/**
+ * Translate the body of all functions into opcodes, stored into Function::statements
+ * The resolution is the result of Resolver::resolve() for this program
+ */
+pub fn lower(program: &mut Program, resolution: &Resolution) {
+    for (name, function) in program.functions.iter_mut() {
+        let scope = resolution.scope(name).expect("The function must be resolved before its lowering");
+
+        function.statements = lower_function(function, scope);
+    }
+}
+
+/**
+ * Translate the body of a resolved function into opcodes, with the scope given by the resolver
+ */
+pub fn lower_function(function: &Function, scope: &FunctionScope) -> Vec<Opcode> {
+    let mut lowering = FunctionLowering {
+        scope,
+        code: Vec::new(),
+        labels: vec![None; scope.labels.len()],
+        label_uses: Vec::new(),
+        switches: Vec::new(),
+    };
+
+    lowering.emit(Opcode::Enter(scope.parameters.len() + scope.autos.len()));
+
+    for (index, auto) in scope.autos.iter().enumerate() {
+        if let Some(size) = auto.size {
+            lowering.emit(Opcode::Vector { local: scope.parameters.len() + index, size: size.max(0) as usize });
+        }
+    }
+
+    lowering.statement(&function.body);
+
+    // falling off the end of the function returns 0
+    lowering.emit(Opcode::Constant(0));
+    lowering.emit(Opcode::Return);
+
+    lowering.finish()
+}
+
+struct FunctionLowering<'a> {
+    scope: &'a FunctionScope,
+    code: Vec<Opcode>,
+    /**
+     * Instruction index of each label of the scope, once lowered
+     */
+    labels: Vec<Option<usize>>,
+    /**
+     * Jumps and constants referencing a label, patched when all labels are lowered
+     */
+    label_uses: Vec<(usize, usize)>,
+    /**
+     * The cases of the enclosing switch statements : the constant and the instruction index
+     */
+    switches: Vec<Vec<(i64, usize)>>,
+}
+
+impl FunctionLowering<'_> {
+    fn emit(&mut self, opcode: Opcode) -> usize {
+        self.code.push(opcode);
+        self.code.len() - 1
+    }
+
+    /**
+     * Set the target of a jump emitted before its target is known
+     */
+    fn patch(&mut self, jump: usize, target: usize) {
+        match &mut self.code[jump] {
+            Opcode::Jump(address) | Opcode::JumpIfZero(address) => *address = target,
+            Opcode::Constant(value) => *value = target as i64,
+            opcode => unreachable!("Cannot patch the target of {:?}", opcode),
+        }
+    }
+
+    /**
+     * Emit an opcode referencing a label, which may not be lowered yet
+     */
+    fn emit_label_use(&mut self, opcode: Opcode, label: usize) {
+        let index = self.emit(opcode);
+
+        self.label_uses.push((index, label));
+    }
+
+    fn finish(mut self) -> Vec<Opcode> {
+        for (index, label) in std::mem::take(&mut self.label_uses) {
+            // all labels of the scope are lowered, since they are declared by the statements of the body
+            let target = self.labels[label].expect("Labels are lowered with the body");
+
+            self.patch(index, target);
+        }
+
+        self.code
+    }
+
+    fn binding(&self, id: NodeId) -> Binding {
+        self.scope.binding(id).expect("The function must be resolved before its lowering")
+    }
+
+    fn statement(&mut self, statement: &SpannedStatement) {
+        match &statement.value {
+            Statement::Auto(_) | Statement::Extern(_) | Statement::Null => {},
+            Statement::Block(statements) => statements.iter().for_each(|statement| self.statement(statement)),
+            Statement::If { condition, then, otherwise } => {
+                self.rvalue(condition);
+
+                let skip_then = self.emit(Opcode::JumpIfZero(0));
+
+                self.statement(then);
+
+                match otherwise {
+                    Some(otherwise) => {
+                        let skip_otherwise = self.emit(Opcode::Jump(0));
+
+                        self.patch(skip_then, self.code.len());
+                        self.statement(otherwise);
+                        self.patch(skip_otherwise, self.code.len());
+                    },
+                    None => self.patch(skip_then, self.code.len()),
+                }
+            },
+            Statement::While { condition, body } => {
+                let start = self.code.len();
+
+                self.rvalue(condition);
+
+                let exit = self.emit(Opcode::JumpIfZero(0));
+
+                self.statement(body);
+                self.emit(Opcode::Jump(start));
+                self.patch(exit, self.code.len());
+            },
+            Statement::Switch { value, body } => {
+                // the body is followed by the comparisons of the value with each case
+                self.rvalue(value);
+
+                let dispatch = self.emit(Opcode::Jump(0));
+
+                self.switches.push(Vec::new());
+                self.statement(body);
+
+                let cases = self.switches.pop().unwrap_or_default();
+                let exit = self.emit(Opcode::Jump(0));
+
+                self.patch(dispatch, self.code.len());
+
+                for (value, target) in cases {
+                    self.emit(Opcode::Case(value, target));
+                }
+
+                // no case matches the value
+                self.emit(Opcode::Pop);
+                self.patch(exit, self.code.len());
+            },
+            Statement::Case { value, statement } => {
+                let value = match &value.value {
+                    Expression::Integer(value) => *value as i64,
+                    Expression::Char(chars) => pack_chars(chars),
+                    _ => unreachable!("The parser only accepts constant cases"),
+                };
+                let target = self.code.len();
+
+                if let Some(cases) = self.switches.last_mut() {
+                    cases.push((value, target));
+                }
+
+                self.statement(statement);
+            },
+            Statement::Label { name, statement } => {
+                if let Some(Binding::Label(label)) = self.scope.lookup(name.value) {
+                    self.labels[label] = Some(self.code.len());
+                }
+
+                self.statement(statement);
+            },
+            Statement::Goto(target) => {
+                let binding = match &target.value {
+                    Expression::Name { id, .. } => self.scope.binding(*id),
+                    _ => None,
+                };
+
+                match binding {
+                    Some(Binding::Label(label)) => self.emit_label_use(Opcode::Jump(0), label),
+                    _ => {
+                        self.rvalue(target);
+                        self.emit(Opcode::JumpIndirect);
+                    },
+                }
+            },
+            Statement::Return(value) => {
+                match value {
+                    Some(value) => self.rvalue(value),
+                    None => {
+                        self.emit(Opcode::Constant(0));
+                    },
+                }
+
+                self.emit(Opcode::Return);
+            },
+            Statement::Expression(expression) => {
+                self.rvalue(expression);
+                self.emit(Opcode::Pop);
+            },
+        }
+    }
+
+    /**
+     * Push the value of an expression
+     */
+    fn rvalue(&mut self, expression: &SpannedExpression) {
+        match &expression.value {
+            Expression::Integer(value) => {
+                self.emit(Opcode::Constant(*value as i64));
+            },
+            Expression::Char(chars) => {
+                self.emit(Opcode::Constant(pack_chars(chars)));
+            },
+            Expression::String(value) => {
+                self.emit(Opcode::String(value.clone()));
+            },
+            Expression::Name { id, .. } => match self.binding(*id) {
+                // the value of a label is its instruction index
+                Binding::Label(label) => self.emit_label_use(Opcode::Constant(0), label),
+                _ => {
+                    self.lvalue(expression);
+                    self.emit(Opcode::Load);
+                },
+            },
+            Expression::Assign { operator, target, value } => {
+                self.lvalue(target);
+
+                if let Some(operator) = operator {
+                    self.emit(Opcode::Duplicate);
+                    self.emit(Opcode::Load);
+                    self.rvalue(value);
+                    self.emit(Opcode::Binary(*operator));
+                } else {
+                    self.rvalue(value);
+                }
+
+                self.emit(Opcode::Store);
+            },
+            Expression::Unary { operator, operand } => {
+                let opcode = match operator {
+                    UnaryOperator::Address => return self.lvalue(operand),
+                    UnaryOperator::Negate => Opcode::Negate,
+                    UnaryOperator::Not => Opcode::Not,
+                    UnaryOperator::Indirection => Opcode::Load,
+                    UnaryOperator::PreIncrement => Opcode::Increment(1),
+                    UnaryOperator::PreDecrement => Opcode::Increment(-1),
+                    UnaryOperator::PostIncrement => Opcode::PostIncrement(1),
+                    UnaryOperator::PostDecrement => Opcode::PostIncrement(-1),
+                };
+
+                if operator.is_lvalue_operator() {
+                    self.lvalue(operand);
+                } else {
+                    self.rvalue(operand);
+                }
+
+                self.emit(opcode);
+            },
+            Expression::Binary { operator, left, right } => {
+                self.rvalue(left);
+                self.rvalue(right);
+                self.emit(Opcode::Binary(*operator));
+            },
+            Expression::Conditional { condition, then, otherwise } => {
+                self.rvalue(condition);
+
+                let skip_then = self.emit(Opcode::JumpIfZero(0));
+
+                self.rvalue(then);
+
+                let skip_otherwise = self.emit(Opcode::Jump(0));
+
+                self.patch(skip_then, self.code.len());
+                self.rvalue(otherwise);
+                self.patch(skip_otherwise, self.code.len());
+            },
+            Expression::Index { .. } => {
+                self.lvalue(expression);
+                self.emit(Opcode::Load);
+            },
+            Expression::Call { function, arguments } => {
+                self.rvalue(function);
+                arguments.iter().for_each(|argument| self.rvalue(argument));
+                self.emit(Opcode::Call(arguments.len()));
+            },
+        }
+    }
+
+    /**
+     * Push the address designated by an expression
+     */
+    fn lvalue(&mut self, expression: &SpannedExpression) {
+        match &expression.value {
+            Expression::Name { id, .. } => {
+                let opcode = match self.binding(*id) {
+                    Binding::Parameter(index) => Opcode::Local(index),
+                    Binding::Auto(index) => Opcode::Local(self.scope.parameters.len() + index),
+                    Binding::Global(name) => Opcode::Global(name),
+                    Binding::Label(_) => unreachable!("A label is not an lvalue"),
+                };
+
+                self.emit(opcode);
+            },
+            Expression::Unary { operator: UnaryOperator::Indirection, operand } => self.rvalue(operand),
+            Expression::Index { vector, index } => {
+                self.rvalue(vector);
+                self.rvalue(index);
+                self.emit(Opcode::Binary(BinaryOperator::Add));
+            },
+            _ => unreachable!("The resolver only accepts lvalues"),
+        }
+    }
+}
@@ -1,16 +1,22 @@
-use std::path::Path;
-use crate::parser::Parser;
+use std::env;
+use std::process::ExitCode;
 
-use crate::tokenizer::Tokenizer;
+use crate::cli::{Options, Status, UsageError};
 
-mod tokenizer;
-mod parser;
+mod cli;
 
-fn main() {
-    let tokenizer = match Tokenizer::from_file(Path::new("example/printn.b")) {
-        Ok(t) => t,
-        Err(e) => panic!("Cannot parse file : {}", e)
+fn main() -> ExitCode {
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(UsageError::Help) => {
+            println!("{}", UsageError::Help);
+            return Status::Success.into();
+        },
+        Err(e) => {
+            eprintln!("{}", e);
+            return Status::Usage.into();
+        }
     };
 
-    Parser::parse(tokenizer.tokens())
+    cli::execute(&options).into()
 }
@@ -1,6 +1,569 @@
+pub use error::ParseError;
 pub use opcode::Opcode;
-pub use parser::Parser;
+pub use parser::{parse_tokens, Parser};
+pub use structure::{Function, Program, Value, Variable};
 
+pub mod ast;
+mod error;
 mod opcode;
 mod structure;
-mod parser;
\ No newline at end of file
+#[allow(clippy::module_inception)]
+mod parser;
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::parser::ast::{BinaryOperator, Expression, SpannedExpression, SpannedStatement, Statement, UnaryOperator};
+    use crate::parser::{parse_tokens, ParseError, Parser, Program, Value, Variable};
+    use crate::tokenizer::{Position, Span, Symbol, Tokenizer};
+
+    fn parse(source: &str) -> Program {
+        Parser::new(Tokenizer::new().stream(source.chars())).parse_program().unwrap()
+    }
+
+    fn parse_errors(source: &str) -> Vec<ParseError> {
+        Parser::new(Tokenizer::new().stream(source.chars())).parse_program().unwrap_err()
+    }
+
+    fn parse_error(source: &str) -> ParseError {
+        let mut errors = parse_errors(source);
+
+        assert_eq!(1, errors.len(), "expecting a single error, got {:?}", errors);
+
+        errors.remove(0)
+    }
+
+    /**
+     * Parse the body of a function containing a single expression statement
+     */
+    fn parse_expression(source: &str) -> SpannedExpression {
+        let program = parse(format!("f() {};", source).as_str());
+
+        match &program.functions["f"].body.value {
+            Statement::Expression(expression) => expression.clone(),
+            statement => panic!("expecting expression, got {:?}", statement),
+        }
+    }
+
+    /**
+     * Display an expression as a fully parenthesized string
+     */
+    fn display(expression: &SpannedExpression) -> String {
+        match &expression.value {
+            Expression::Integer(i) => i.to_string(),
+            Expression::Char(chars) => format!("'{}'", chars.iter().filter(|c| **c != '\0').collect::<String>()),
+            Expression::String(s) => format!("\"{}\"", s),
+            Expression::Name { name, .. } => name.to_string(),
+            Expression::Assign { operator, target, value } => {
+                format!("({} ={} {})", display(target), operator.map(|o| o.symbol()).unwrap_or(""), display(value))
+            }
+            Expression::Unary { operator, operand } => match operator {
+                UnaryOperator::PostIncrement => format!("({}++)", display(operand)),
+                UnaryOperator::PostDecrement => format!("({}--)", display(operand)),
+                UnaryOperator::PreIncrement => format!("(++{})", display(operand)),
+                UnaryOperator::PreDecrement => format!("(--{})", display(operand)),
+                UnaryOperator::Negate => format!("(-{})", display(operand)),
+                UnaryOperator::Not => format!("(!{})", display(operand)),
+                UnaryOperator::Address => format!("(&{})", display(operand)),
+                UnaryOperator::Indirection => format!("(*{})", display(operand)),
+            },
+            Expression::Binary { operator, left, right } => {
+                format!("({} {} {})", display(left), operator.symbol(), display(right))
+            }
+            Expression::Conditional { condition, then, otherwise } => {
+                format!("({} ? {} : {})", display(condition), display(then), display(otherwise))
+            }
+            Expression::Index { vector, index } => format!("{}[{}]", display(vector), display(index)),
+            Expression::Call { function, arguments } => {
+                format!("{}({})", display(function), arguments.iter().map(display).collect::<Vec<_>>().join(", "))
+            }
+        }
+    }
+
+    #[test]
+    fn parse_file() {
+        let tokenizer = Tokenizer::new().read_file(Path::new("example/printn.b")).unwrap();
+        let program = parse_tokens(tokenizer.into_tokens()).unwrap();
+
+        let function = &program.functions["printn"];
+
+        assert_eq!("printn", function.name.as_str());
+        assert_eq!(vec!["n", "b"], function.arguments.iter().map(|a| a.value.as_str()).collect::<Vec<_>>());
+        assert_eq!(Span::new(Position::new(216, 6, 1), Position::new(403, 13, 2)), function.span);
+
+        let statements = match &function.body.value {
+            Statement::Block(statements) => statements,
+            statement => panic!("expecting block, got {:?}", statement),
+        };
+
+        assert_eq!(4, statements.len());
+
+        match &statements[0].value {
+            Statement::Extern(names) => assert_eq!("putchar", names[0].value.as_str()),
+            statement => panic!("expecting extrn, got {:?}", statement),
+        }
+
+        match &statements[1].value {
+            Statement::Auto(variables) => {
+                assert_eq!("a", variables[0].name.value.as_str());
+                assert_eq!(None, variables[0].size);
+            },
+            statement => panic!("expecting auto, got {:?}", statement),
+        }
+
+        match &statements[2].value {
+            Statement::If { condition, then, otherwise } => {
+                assert_eq!("(a = (n / b))", display(condition));
+                assert_eq!(None, *otherwise);
+
+                match &then.value {
+                    Statement::Expression(e) => assert_eq!("printn(a, b)", display(e)),
+                    statement => panic!("expecting expression, got {:?}", statement),
+                }
+            },
+            statement => panic!("expecting if, got {:?}", statement),
+        }
+
+        match &statements[3].value {
+            Statement::Expression(e) => {
+                assert_eq!("putchar(((n % b) + '0'))", display(e));
+                assert_eq!(Span::new(Position::new(382, 12, 9), Position::new(400, 12, 27)), e.span);
+            },
+            statement => panic!("expecting expression, got {:?}", statement),
+        }
+    }
+
+    #[test]
+    fn arithmetic_precedence() {
+        assert_eq!("((a + (b * c)) - (d % e))", display(&parse_expression("a + b * c - d % e")));
+        assert_eq!("((a << (b + c)) == (d >> e))", display(&parse_expression("a << b + c == d >> e")));
+        assert_eq!("(((-a) * (*b)) + (!(&c)))", display(&parse_expression("-a * *b + !&c")));
+        assert_eq!("((++(a++)) - (--b))", display(&parse_expression("++a++ - --b")));
+        assert_eq!("(a * (b + c))", display(&parse_expression("a * (b + c)")));
+    }
+
+    #[test]
+    fn assignments() {
+        assert_eq!("(a = (b = (c + 1)))", display(&parse_expression("a = b = c + 1")));
+        assert_eq!("(a =+ (b =<< 2))", display(&parse_expression("a =+ b =<< 2")));
+        assert_eq!("((*p) === (a != b))", display(&parse_expression("*p === a != b")));
+
+        match parse_expression("x =- 1").value {
+            Expression::Assign { operator, .. } => assert_eq!(Some(BinaryOperator::Subtract), operator),
+            e => panic!("expecting assignment, got {:?}", e),
+        }
+    }
+
+    fn parse_global(source: &str) -> Variable {
+        parse(source).globals.into_values().next().unwrap()
+    }
+
+    #[test]
+    fn globals() {
+        assert_eq!(
+            Variable::Atomic { name: Symbol::intern("x"), initial: vec![], span: Span::new(Position::new(0, 1, 1), Position::new(2, 1, 3)) },
+            parse_global("x;")
+        );
+
+        match parse_global("x 5;") {
+            Variable::Atomic { name, initial, .. } => {
+                assert_eq!("x", name.as_str());
+                assert_eq!(vec![Value::Constant(5)], initial);
+            },
+            v => panic!("expecting scalar, got {:?}", v),
+        }
+
+        match parse_global("msg \"hello\", 'ab', -3, x;") {
+            Variable::Atomic { initial, .. } => assert_eq!(
+                vec![Value::String(String::from("hello")), Value::Char(['a', 'b', '\0', '\0']), Value::Constant(-3), Value::Variable(Symbol::intern("x"))],
+                initial
+            ),
+            v => panic!("expecting scalar, got {:?}", v),
+        }
+
+        match parse_global("msg \"a*nb*\"c\", 'x*n';") {
+            Variable::Atomic { initial, .. } => assert_eq!(
+                vec!["\"a*nb*\"c\"", "'x*n'"],
+                initial.iter().map(|value| value.to_string()).collect::<Vec<_>>()
+            ),
+            v => panic!("expecting scalar, got {:?}", v),
+        }
+    }
+
+    #[test]
+    fn vectors() {
+        let vector = parse_global("v[10] 1, 2, 3;");
+        assert_eq!(Some(10), vector.vector_size());
+        assert_eq!(Span::new(Position::new(0, 1, 1), Position::new(14, 1, 15)), vector.span());
+
+        match vector {
+            Variable::Array { name, size, initial, .. } => {
+                assert_eq!("v", name.as_str());
+                assert_eq!(Some(10), size);
+                assert_eq!(vec![Value::Constant(1), Value::Constant(2), Value::Constant(3)], initial);
+            },
+            v => panic!("expecting vector, got {:?}", v),
+        }
+
+        let vector = parse_global("v[] 1, 2, 3;");
+        assert_eq!(Some(3), vector.vector_size());
+
+        let vector = parse_global("v[2] a, b, c;");
+        assert_eq!(Some(3), vector.vector_size());
+
+        match parse_global("v[5];") {
+            Variable::Array { size, initial, .. } => {
+                assert_eq!(Some(5), size);
+                assert!(initial.is_empty());
+            },
+            v => panic!("expecting vector, got {:?}", v),
+        }
+    }
+
+    #[test]
+    fn multiple_definitions() {
+        let program = parse("main() { f(x); }\nx 5;\nf(a) a;\nv[3];");
+
+        assert_eq!(vec!["f", "main"], program.functions.keys().collect::<Vec<_>>());
+        assert_eq!(vec!["v", "x"], program.globals.keys().collect::<Vec<_>>());
+        assert_eq!(Some(Span::new(Position::new(17, 2, 1), Position::new(21, 2, 5))), program.definition_span("x"));
+        assert_eq!(None, program.definition_span("a"));
+    }
+
+    #[test]
+    fn empty_program() {
+        let program = parse("  /* nothing */ ");
+
+        assert!(program.functions.is_empty());
+        assert!(program.globals.is_empty());
+    }
+
+    #[test]
+    fn duplicate_definition() {
+        let error = parse_error("x 5;\nf() g();\nx() {}");
+
+        assert_eq!("Duplicate definition of `x`", error.message());
+        assert_eq!(Span::new(Position::new(14, 3, 1), Position::new(15, 3, 2)), error.span());
+        assert_eq!(Some(("previously defined", Span::new(Position::new(0, 1, 1), Position::new(4, 1, 5)))), error.related());
+        assert_eq!("ParseError: Duplicate definition of `x` at 3:1 (previously defined at 1:1)", error.to_string());
+    }
+
+    #[test]
+    fn unexpected_end_of_file() {
+        let error = parse_error("main() {\n  f(x);\n");
+
+        assert_eq!("Unexpected end of file", error.message());
+        assert_eq!(Span::new(Position::new(16, 2, 8), Position::new(16, 2, 8)), error.span());
+
+        assert_eq!("Unexpected end of file", parse_error("x 1,").message());
+        assert_eq!("Unexpected end of file", parse_error("f(a, b").message());
+    }
+
+    /**
+     * Parse the statements of the body of a function "f() { ... }"
+     */
+    fn parse_body(source: &str) -> Vec<SpannedStatement> {
+        let program = parse(format!("f() {{ {} }}", source).as_str());
+
+        match &program.functions["f"].body.value {
+            Statement::Block(statements) => statements.clone(),
+            statement => panic!("expecting block, got {:?}", statement),
+        }
+    }
+
+    fn display_statement(statement: &SpannedStatement) -> String {
+        match &statement.value {
+            Statement::Expression(expression) => display(expression),
+            statement => panic!("expecting expression, got {:?}", statement),
+        }
+    }
+
+    #[test]
+    fn while_statement() {
+        let statements = parse_body("while (i != 10) i++;");
+
+        match &statements[0].value {
+            Statement::While { condition, body } => {
+                assert_eq!("(i != 10)", display(condition));
+                assert_eq!("(i++)", display_statement(body));
+            },
+            statement => panic!("expecting while, got {:?}", statement),
+        }
+    }
+
+    #[test]
+    fn switch_statement() {
+        let statements = parse_body("switch (c) { case 'a': x = 1; case 2: case 3: return (c); }");
+
+        let cases = match &statements[0].value {
+            Statement::Switch { value, body } => {
+                assert_eq!("c", display(value));
+
+                match &body.value {
+                    Statement::Block(cases) => cases.clone(),
+                    statement => panic!("expecting block, got {:?}", statement),
+                }
+            },
+            statement => panic!("expecting switch, got {:?}", statement),
+        };
+
+        assert_eq!(2, cases.len());
+
+        match &cases[0].value {
+            Statement::Case { value, statement } => {
+                assert_eq!("'a'", display(value));
+                assert_eq!("(x = 1)", display_statement(statement));
+            },
+            statement => panic!("expecting case, got {:?}", statement),
+        }
+
+        match &cases[1].value {
+            Statement::Case { value, statement } => {
+                assert_eq!("2", display(value));
+
+                match &statement.value {
+                    Statement::Case { value, statement } => {
+                        assert_eq!("3", display(value));
+                        assert!(matches!(statement.value, Statement::Return(Some(_))));
+                    },
+                    statement => panic!("expecting case, got {:?}", statement),
+                }
+            },
+            statement => panic!("expecting case, got {:?}", statement),
+        }
+    }
+
+    #[test]
+    fn labels_and_goto() {
+        let statements = parse_body("loop: x++; goto loop; goto *p;");
+
+        match &statements[0].value {
+            Statement::Label { name, statement } => {
+                assert_eq!("loop", name.value.as_str());
+                assert_eq!(Span::new(Position::new(6, 1, 7), Position::new(10, 1, 11)), name.span);
+                assert_eq!("(x++)", display_statement(statement));
+            },
+            statement => panic!("expecting label, got {:?}", statement),
+        }
+
+        match &statements[1].value {
+            Statement::Goto(target) => assert_eq!("loop", display(target)),
+            statement => panic!("expecting goto, got {:?}", statement),
+        }
+
+        match &statements[2].value {
+            Statement::Goto(target) => assert_eq!("(*p)", display(target)),
+            statement => panic!("expecting goto, got {:?}", statement),
+        }
+    }
+
+    #[test]
+    fn return_statement() {
+        let statements = parse_body("return; return (a + 1);");
+
+        assert_eq!(Statement::Return(None), statements[0].value);
+
+        match &statements[1].value {
+            Statement::Return(Some(value)) => {
+                assert_eq!("(a + 1)", display(value));
+                assert_eq!(Span::new(Position::new(22, 1, 23), Position::new(27, 1, 28)), value.span);
+            },
+            statement => panic!("expecting return, got {:?}", statement),
+        }
+        assert_eq!(Span::new(Position::new(14, 1, 15), Position::new(29, 1, 30)), statements[1].span);
+
+        assert_eq!("Unexpected `a`, expecting `(`", parse_error("f() return a;").message());
+    }
+
+    #[test]
+    fn if_else_statement() {
+        let statements = parse_body("if (a) x = 1; else x = 2; if (b) ;");
+
+        match &statements[0].value {
+            Statement::If { condition, then, otherwise: Some(otherwise) } => {
+                assert_eq!("a", display(condition));
+                assert_eq!("(x = 1)", display_statement(then));
+                assert_eq!("(x = 2)", display_statement(otherwise));
+                assert_eq!(Span::new(Position::new(6, 1, 7), Position::new(31, 1, 32)), statements[0].span);
+            },
+            statement => panic!("expecting if else, got {:?}", statement),
+        }
+
+        match &statements[1].value {
+            Statement::If { then, otherwise: None, .. } => assert_eq!(Statement::Null, then.value),
+            statement => panic!("expecting if, got {:?}", statement),
+        }
+    }
+
+    #[test]
+    fn dangling_else() {
+        let statements = parse_body("if (a) if (b) x; else y;");
+
+        match &statements[0].value {
+            Statement::If { then, otherwise: None, .. } => match &then.value {
+                Statement::If { condition, otherwise: Some(otherwise), .. } => {
+                    assert_eq!("b", display(condition));
+                    assert_eq!("y", display_statement(otherwise));
+                },
+                statement => panic!("expecting inner if with else, got {:?}", statement),
+            },
+            statement => panic!("expecting outer if without else, got {:?}", statement),
+        }
+    }
+
+    #[test]
+    fn null_statement() {
+        let statements = parse_body("; while (*p++) ; {}");
+
+        assert_eq!(Statement::Null, statements[0].value);
+        assert_eq!(Span::new(Position::new(6, 1, 7), Position::new(7, 1, 8)), statements[0].span);
+
+        match &statements[1].value {
+            Statement::While { body, .. } => assert_eq!(Statement::Null, body.value),
+            statement => panic!("expecting while, got {:?}", statement),
+        }
+
+        assert_eq!(Statement::Block(vec![]), statements[2].value);
+        assert_eq!(Statement::Null, parse("f();").functions["f"].body.value);
+    }
+
+    #[test]
+    fn relational_and_bitwise_precedence() {
+        assert_eq!("((a < b) == (c >= d))", display(&parse_expression("a < b == c >= d")));
+        assert_eq!("((a <= (b << 1)) != (c > (d >> 2)))", display(&parse_expression("a <= b << 1 != c > d >> 2")));
+        assert_eq!("((a == b) & (c != d))", display(&parse_expression("a == b & c != d")));
+        assert_eq!("((a & b) | (c & d))", display(&parse_expression("a & b | c & d")));
+        assert_eq!("(((a | b) | c) & d)", display(&parse_expression("(a | b | c) & d")));
+        assert_eq!("((-(*p)) & (&x))", display(&parse_expression("-*p & &x")));
+    }
+
+    #[test]
+    fn left_associativity() {
+        assert_eq!("((a - b) - c)", display(&parse_expression("a - b - c")));
+        assert_eq!("((a / b) * c)", display(&parse_expression("a / b * c")));
+        assert_eq!("((a < b) < c)", display(&parse_expression("a < b < c")));
+        assert_eq!("((a << b) >> c)", display(&parse_expression("a << b >> c")));
+    }
+
+    #[test]
+    fn conditional_expression() {
+        assert_eq!("((a < b) ? a : b)", display(&parse_expression("a < b ? a : b")));
+        assert_eq!("(a ? b : (c ? d : e))", display(&parse_expression("a ? b : c ? d : e")));
+        assert_eq!("((a | b) ? (x = 1) : 2)", display(&parse_expression("a | b ? x = 1 : 2")));
+        assert_eq!("(m = (a ? b : c))", display(&parse_expression("m = a ? b : c")));
+    }
+
+    #[test]
+    fn subscripts() {
+        assert_eq!("v[(i + 1)]", display(&parse_expression("v[i + 1]")));
+        assert_eq!("m[i][j]", display(&parse_expression("m[i][j]")));
+        assert_eq!("(v[i] = (w[j]++))", display(&parse_expression("v[i] = w[j]++")));
+        assert_eq!("(-f(x)[2])", display(&parse_expression("-f(x)[2]")));
+        assert_eq!("(*v[0])", display(&parse_expression("*v[0]")));
+
+        let expression = parse_expression("v[i]");
+        assert_eq!(Span::new(Position::new(4, 1, 5), Position::new(8, 1, 9)), expression.span);
+    }
+
+    #[test]
+    fn expected_and_found() {
+        let error = parse_error("f(a b) ;");
+
+        assert_eq!(Some("`,` or `)`"), error.expected());
+        assert_eq!(Some("`b`"), error.found());
+        assert_eq!(Span::new(Position::new(4, 1, 5), Position::new(5, 1, 6)), error.span());
+        assert_eq!("ParseError: Unexpected `b`, expecting `,` or `)` at 1:5", error.to_string());
+    }
+
+    #[test]
+    fn recover_from_statement_errors() {
+        let errors = parse_errors("main() {\n  a = ;\n  b = 1;\n  if (x y) { z; t; }\n  f(1 2);\n}\ng() { h(; }");
+
+        assert_eq!(
+            vec![
+                ("Unexpected `;`, expecting expression", Position::new(15, 2, 7)),
+                ("Unexpected `y`, expecting `)`", Position::new(34, 4, 9)),
+                ("Unexpected `2`, expecting `,` or `)`", Position::new(53, 5, 7)),
+                ("Unexpected `;`, expecting expression", Position::new(67, 7, 9)),
+            ],
+            errors.iter().map(|e| (e.message(), e.span().start)).collect::<Vec<_>>()
+        );
+
+        let errors = parse_errors("f() { a = ; b = ; c = ; }");
+
+        assert_eq!(
+            vec![Position::new(10, 1, 11), Position::new(16, 1, 17), Position::new(22, 1, 23)],
+            errors.iter().map(|e| e.span().start).collect::<Vec<_>>()
+        );
+
+        let errors = parse_errors("f() { a = ; b = (1; c; } g( { } h() { x y; }");
+
+        assert_eq!(
+            vec![
+                "Unexpected `;`, expecting expression",
+                "Unexpected `;`, expecting `)`",
+                "Unexpected `{`, expecting argument name or `)`",
+                "Unexpected `y`, expecting `;`",
+            ],
+            errors.iter().map(|e| e.message()).collect::<Vec<_>>()
+        );
+
+        // the "}" ending the statement still closes the block
+        let errors = parse_errors("f() { a = } g() { b = ; }");
+
+        assert_eq!(
+            vec!["Unexpected `}`, expecting expression", "Unexpected `;`, expecting expression"],
+            errors.iter().map(|e| e.message()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn recover_from_definition_errors() {
+        let errors = parse_errors("x 1 2;\n} f() ;\nv[a];\nmain() { return (1); }\nmain();");
+
+        assert_eq!(
+            vec![
+                "Unexpected `2`, expecting `,` or `;`",
+                "Unexpected `}`, expecting symbol",
+                "Unexpected `a`, expecting vector size or `]`",
+                "Duplicate definition of `main`",
+            ],
+            errors.iter().map(|e| e.message()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn report_end_of_file_once() {
+        let errors = parse_errors("main() {\n  if (a) {\n    b;");
+
+        assert_eq!(1, errors.len());
+        assert_eq!("Unexpected end of file", errors[0].message());
+    }
+
+    #[test]
+    fn token_error_ends_parsing() {
+        let errors = parse_errors("f() { a = ; b = 99999999999; c = }");
+
+        assert_eq!(2, errors.len());
+        assert_eq!("Unexpected `;`, expecting expression", errors[0].message());
+        assert_eq!("Integer literal overflow near `99999999999`", errors[1].message());
+        assert_eq!(Span::new(Position::new(16, 1, 17), Position::new(27, 1, 28)), errors[1].span());
+    }
+
+    #[test]
+    fn parse_from_token_vector_stream_or_reader() {
+        let source = "main() { auto a; a = 1; lbl: a =+ f(a)[2]; goto lbl; }";
+
+        let from_vector = parse_tokens(Tokenizer::from_string(source).unwrap().into_tokens()).unwrap();
+        let from_stream = parse(source);
+        let from_reader = Parser::new(Tokenizer::new().read(source.as_bytes())).parse_program().unwrap();
+
+        assert_eq!(from_vector.functions["main"].body, from_stream.functions["main"].body);
+        assert_eq!(from_reader.functions["main"].body, from_stream.functions["main"].body);
+
+        let errors = Parser::new(Tokenizer::new().read(&b"f() { a = \"\xFF\"; }"[..])).parse_program().unwrap_err();
+
+        assert_eq!(vec![String::from("ParseError: Invalid UTF-8 sequence near `\\xFF` at 1:12")], errors.iter().map(|e| e.to_string()).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,308 @@
+use crate::tokenizer::{Operator, Spanned, Symbol};
+
+/**
+ * A statement node, with its location into the source
+ */
+pub type SpannedStatement = Spanned<Statement>;
+
+/**
+ * An expression node, with its location into the source
+ */
+pub type SpannedExpression = Spanned<Expression>;
+
+/**
+ * Identifier of a name expression, unique into the parsed program
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(pub u32);
+
+/**
+ * All statements of a function body
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    /**
+     * Declare local variables : auto a, b 10;
+     */
+    Auto(Vec<AutoVariable>),
+
+    /**
+     * Declare external symbols : extrn putchar, printf;
+     */
+    Extern(Vec<Spanned<Symbol>>),
+
+    /**
+     * A compound statement : { ... }
+     */
+    Block(Vec<SpannedStatement>),
+
+    /**
+     * The if conditional statement : if (condition) then  or  if (condition) then else otherwise
+     * A dangling else is associated with the nearest if
+     */
+    If {
+        condition: SpannedExpression,
+        then: Box<SpannedStatement>,
+        otherwise: Option<Box<SpannedStatement>>,
+    },
+
+    /**
+     * The while loop : while (condition) body
+     */
+    While {
+        condition: SpannedExpression,
+        body: Box<SpannedStatement>,
+    },
+
+    /**
+     * The switch statement : switch (value) body
+     * The body should contains case statements
+     */
+    Switch {
+        value: SpannedExpression,
+        body: Box<SpannedStatement>,
+    },
+
+    /**
+     * A branch of a switch statement : case constant: statement
+     * The value is a constant integer or char
+     */
+    Case {
+        value: SpannedExpression,
+        statement: Box<SpannedStatement>,
+    },
+
+    /**
+     * A labeled statement : name: statement
+     */
+    Label {
+        name: Spanned<Symbol>,
+        statement: Box<SpannedStatement>,
+    },
+
+    /**
+     * Jump to a label : goto name;
+     * The target may be any rvalue evaluating to a label address
+     */
+    Goto(SpannedExpression),
+
+    /**
+     * Leave the function : return;  or  return (value);
+     */
+    Return(Option<SpannedExpression>),
+
+    /**
+     * An expression used as statement : expr;
+     */
+    Expression(SpannedExpression),
+
+    /**
+     * The empty statement : ;
+     */
+    Null,
+}
+
+/**
+ * A local variable declared using auto
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutoVariable {
+    pub name: Spanned<Symbol>,
+
+    /**
+     * When a constant follows the name, a vector of this size is allocated
+     */
+    pub size: Option<i32>,
+}
+
+/**
+ * All expressions (i.e. rvalues and lvalues)
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    /**
+     * A constant integer
+     */
+    Integer(i32),
+
+    /**
+     * A character constant, with at most 4 characters
+     */
+    Char([char; 4]),
+
+    /**
+     * A string constant
+     */
+    String(String),
+
+    /**
+     * Reference to a variable, function or label
+     * The id identifies the expression when binding it to its declaration, see Resolver
+     */
+    Name {
+        name: Symbol,
+        id: NodeId,
+    },
+
+    /**
+     * Simple assignment (operator is None) or compound assignment like "=+"
+     */
+    Assign {
+        operator: Option<BinaryOperator>,
+        target: Box<SpannedExpression>,
+        value: Box<SpannedExpression>,
+    },
+
+    /**
+     * Prefix or postfix operation on a single value
+     */
+    Unary {
+        operator: UnaryOperator,
+        operand: Box<SpannedExpression>,
+    },
+
+    /**
+     * Binary operation
+     */
+    Binary {
+        operator: BinaryOperator,
+        left: Box<SpannedExpression>,
+        right: Box<SpannedExpression>,
+    },
+
+    /**
+     * Ternary operator : condition ? then : otherwise
+     */
+    Conditional {
+        condition: Box<SpannedExpression>,
+        then: Box<SpannedExpression>,
+        otherwise: Box<SpannedExpression>,
+    },
+
+    /**
+     * Vector access : vector[index]
+     * This is strictly equivalent to *(vector + index)
+     */
+    Index {
+        vector: Box<SpannedExpression>,
+        index: Box<SpannedExpression>,
+    },
+
+    /**
+     * Function call : function(arguments...)
+     */
+    Call {
+        function: Box<SpannedExpression>,
+        arguments: Vec<SpannedExpression>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOperator {
+    /**
+     * Arithmetic negation "-"
+     */
+    Negate,
+
+    /**
+     * Logical negation "!"
+     */
+    Not,
+
+    /**
+     * Get the address of an lvalue "&"
+     */
+    Address,
+
+    /**
+     * Get the value stored at the given address "*"
+     */
+    Indirection,
+
+    PreIncrement,
+    PreDecrement,
+    PostIncrement,
+    PostDecrement,
+}
+
+impl UnaryOperator {
+    /**
+     * Check if the operand must be an lvalue, i.e. "&" and the increments
+     */
+    pub fn is_lvalue_operator(&self) -> bool {
+        !matches!(self, UnaryOperator::Negate | UnaryOperator::Not | UnaryOperator::Indirection)
+    }
+}
+
+/**
+ * Binary operators, from the lowest to the highest precedence
+ * Note: unlike C, "|" and "&" are used for both bitwise and logical operations
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Or,
+    And,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    ShiftLeft,
+    ShiftRight,
+    Add,
+    Subtract,
+    Modulo,
+    Multiply,
+    Divide,
+}
+
+impl BinaryOperator {
+    /**
+     * Get the binary operation of the operator token
+     * Returns None if the operator cannot be used as binary operator
+     */
+    pub fn from_operator(operator: Operator) -> Option<BinaryOperator> {
+        match operator {
+            Operator::Pipe => Some(BinaryOperator::Or),
+            Operator::Ampersand => Some(BinaryOperator::And),
+            Operator::Equal => Some(BinaryOperator::Equal),
+            Operator::NotEqual => Some(BinaryOperator::NotEqual),
+            Operator::Less => Some(BinaryOperator::Less),
+            Operator::LessEqual => Some(BinaryOperator::LessEqual),
+            Operator::Greater => Some(BinaryOperator::Greater),
+            Operator::GreaterEqual => Some(BinaryOperator::GreaterEqual),
+            Operator::ShiftLeft => Some(BinaryOperator::ShiftLeft),
+            Operator::ShiftRight => Some(BinaryOperator::ShiftRight),
+            Operator::Plus => Some(BinaryOperator::Add),
+            Operator::Minus => Some(BinaryOperator::Subtract),
+            Operator::Percent => Some(BinaryOperator::Modulo),
+            Operator::Star => Some(BinaryOperator::Multiply),
+            Operator::Slash => Some(BinaryOperator::Divide),
+            _ => None,
+        }
+    }
+
+    /**
+     * Get the source representation of the operator
+     */
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOperator::Or => "|",
+            BinaryOperator::And => "&",
+            BinaryOperator::Equal => "==",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::Less => "<",
+            BinaryOperator::LessEqual => "<=",
+            BinaryOperator::Greater => ">",
+            BinaryOperator::GreaterEqual => ">=",
+            BinaryOperator::ShiftLeft => "<<",
+            BinaryOperator::ShiftRight => ">>",
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Modulo => "%",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use std::convert::Infallible;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use crate::tokenizer::{ReadError, Span, TokenError};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    message: String,
+    span: Span,
+    /**
+     * If the error is caused by an unexpected token :
+     * the description of the expected token, and the token found instead
+     */
+    unexpected: Option<Box<(String, String)>>,
+    /**
+     * Another location of the source related to the error, with its description
+     * For example the first definition of a duplicate symbol
+     */
+    related: Option<Box<(String, Span)>>,
+}
+
+impl ParseError {
+    pub fn new(message: String, span: Span) -> ParseError {
+        ParseError {
+            message,
+            span,
+            unexpected: None,
+            related: None,
+        }
+    }
+
+    /**
+     * Create an error for an unexpected token
+     */
+    pub fn unexpected(found: String, expected: String, span: Span) -> ParseError {
+        ParseError {
+            message: format!("Unexpected {}, expecting {}", found, expected),
+            span,
+            unexpected: Some(Box::new((expected, found))),
+            related: None,
+        }
+    }
+
+    pub fn with_related(mut self, description: String, span: Span) -> ParseError {
+        self.related = Some(Box::new((description, span)));
+        self
+    }
+
+    pub fn message(&self) -> &str {
+        self.message.as_str()
+    }
+
+    /**
+     * The location of the error
+     */
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn expected(&self) -> Option<&str> {
+        self.unexpected.as_deref().map(|(expected, _)| expected.as_str())
+    }
+
+    pub fn found(&self) -> Option<&str> {
+        self.unexpected.as_deref().map(|(_, found)| found.as_str())
+    }
+
+    pub fn related(&self) -> Option<(&str, Span)> {
+        self.related.as_deref().map(|(description, span)| (description.as_str(), *span))
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ParseError: {} at {}", self.message, self.span)?;
+
+        if let Some((description, span)) = self.related.as_deref() {
+            write!(f, " ({} at {})", description, span)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for ParseError {
+
+}
+
+/**
+ * The tokenizer cannot recover from an error, so it ends the parsing
+ */
+impl From<TokenError> for ParseError {
+    fn from(error: TokenError) -> Self {
+        ParseError::new(format!("{} near `{}`", error.message(), error.text()), error.span())
+    }
+}
+
+/**
+ * The reader cannot be read anymore, so it ends the parsing
+ * The location of an I/O error is unknown
+ */
+impl From<ReadError> for ParseError {
+    fn from(error: ReadError) -> Self {
+        match error {
+            ReadError::Io(e) => ParseError::new(format!("Cannot read the source: {}", e), Span::default()),
+            ReadError::Token(e) => e.into(),
+        }
+    }
+}
+
+/**
+ * A stream of tokens which cannot fail
+ */
+impl From<Infallible> for ParseError {
+    fn from(error: Infallible) -> Self {
+        match error {}
+    }
+}
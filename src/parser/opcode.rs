@@ -1,3 +1,152 @@
+use std::fmt::{Display, Formatter};
+
+use crate::parser::ast::BinaryOperator;
+use crate::tokenizer::{quote, Symbol};
+
+/**
+ * Instruction of the intermediate representation of the functions
+ *
+ * The instructions operate on a stack of words. Memory is addressed by words, so the address
+ * following a vector element is the address of the next element.
+ * Jump targets are indexes into the instructions of the function.
+ */
+#[derive(Debug, Clone, PartialEq)]
 pub enum Opcode {
+    /**
+     * Reserve the local words of the function : its arguments, then its auto variables
+     * This is the first instruction of each function
+     */
+    Enter(usize),
+
+    /**
+     * Allocate an auto vector of the given size, and store its address into the local word
+     */
+    Vector {
+        local: usize,
+        size: usize,
+    },
+
+    /**
+     * Push a constant word
+     * Characters of a char constant are packed into the word, the last one in the lowest byte
+     */
+    Constant(i64),
+
+    /**
+     * Push the address of a string literal
+     */
+    String(String),
+
+    /**
+     * Push the address of a local word
+     */
+    Local(usize),
+
+    /**
+     * Push the address of a global word
+     * The word of a function name holds the function itself
+     */
+    Global(Symbol),
+
+    /**
+     * Pop an address, and push the word stored at this address
+     */
+    Load,
+
+    /**
+     * Pop a value and an address, store the value at the address, and push the value back
+     */
+    Store,
+
+    /**
+     * Pop an address, add the constant to the word at this address, and push the new value
+     */
+    Increment(i64),
+
+    /**
+     * Pop an address, add the constant to the word at this address, and push the previous value
+     */
+    PostIncrement(i64),
+
+    /**
+     * Push a copy of the word on top of the stack
+     */
+    Duplicate,
+
+    /**
+     * Discard the word on top of the stack
+     */
+    Pop,
+
+    Negate,
+
+    /**
+     * Logical negation : push 1 if the popped word is 0, else 0
+     */
+    Not,
+
+    /**
+     * Pop the right operand, then the left operand, and push the result
+     * Comparisons push 1 if true, else 0
+     */
+    Binary(BinaryOperator),
+
+    Jump(usize),
+
+    /**
+     * Pop a word, and jump if it's zero
+     */
+    JumpIfZero(usize),
+
+    /**
+     * Pop a label value, and jump to it
+     */
+    JumpIndirect,
+
+    /**
+     * If the word on top of the stack equals the constant, pop it and jump to the target
+     */
+    Case(i64, usize),
+
+    /**
+     * Pop the given number of arguments, and the function to call
+     * The arguments are pushed in order, and the return value is pushed back
+     */
+    Call(usize),
+
+    /**
+     * Pop the return value, and leave the function
+     */
+    Return,
+}
 
+impl Display for Opcode {
+    /**
+     * Display the instruction in the listing of the build command
+     */
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Opcode::Enter(size) => write!(f, "enter {}", size),
+            Opcode::Vector { local, size } => write!(f, "vector {} {}", local, size),
+            Opcode::Constant(value) => write!(f, "const {}", value),
+            Opcode::String(value) => write!(f, "string {}", quote(value.chars(), '"')),
+            Opcode::Local(index) => write!(f, "local {}", index),
+            Opcode::Global(name) => write!(f, "global {}", name),
+            Opcode::Load => write!(f, "load"),
+            Opcode::Store => write!(f, "store"),
+            Opcode::Increment(value) => write!(f, "inc {}", value),
+            Opcode::PostIncrement(value) => write!(f, "postinc {}", value),
+            Opcode::Duplicate => write!(f, "dup"),
+            Opcode::Pop => write!(f, "pop"),
+            Opcode::Negate => write!(f, "neg"),
+            Opcode::Not => write!(f, "not"),
+            Opcode::Binary(operator) => write!(f, "binary {}", operator.symbol()),
+            Opcode::Jump(target) => write!(f, "jump {}", target),
+            Opcode::JumpIfZero(target) => write!(f, "jz {}", target),
+            Opcode::JumpIndirect => write!(f, "jump *"),
+            Opcode::Case(value, target) => write!(f, "case {} {}", value, target),
+            Opcode::Call(count) => write!(f, "call {}", count),
+            Opcode::Return => write!(f, "ret"),
+        }
+    }
 }
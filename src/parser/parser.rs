@@ -1,390 +1,934 @@
-use std::thread::current;
-use crate::parser::structure::Program;
-use crate::tokenizer::Token;
+use std::collections::VecDeque;
+use std::convert::Infallible;
+
+use crate::parser::ast::{AutoVariable, BinaryOperator, Expression, NodeId, SpannedExpression, SpannedStatement, Statement, UnaryOperator};
+use crate::parser::{Function, ParseError, Program, Value, Variable};
+use crate::tokenizer::{Operator, Span, Spanned, Symbol, Token};
+
+/**
+ * Build the program from the tokens
+ * Tokens are pulled from the iterator as the parsing goes, so it can be fed by a Tokenizer stream
+ */
+pub struct Parser<I> {
+    tokens: I,
+    /**
+     * Tokens pulled from the iterator, but not consumed yet
+     * It contains at most the current token, and a token put back by rollback()
+     */
+    lookahead: VecDeque<Spanned<Token>>,
+    /**
+     * Location of the last consumed token
+     */
+    previous: Span,
+    /**
+     * The iterator has no more tokens, or has failed
+     */
+    exhausted: bool,
+    /**
+     * The iterator has failed : the source is truncated, so the following errors are not reported
+     */
+    failed: bool,
+    /**
+     * Identifier of the next name expression
+     */
+    next_id: u32,
+    errors: Vec<ParseError>,
+}
+
+type Result<T> = std::result::Result<T, ParseError>;
 
-pub struct Parser {
-    tokens: Vec<Token>,
-    cursor: usize
+/**
+ * Parse all definitions of the program
+ * On syntax error, the parser skips to the next statement and continue,
+ * so all errors of the source are returned
+ */
+pub fn parse_tokens<T: IntoIterator<Item = Spanned<Token>>>(tokens: T) -> std::result::Result<Program, Vec<ParseError>> {
+    Parser::new(tokens.into_iter().map(Ok::<_, Infallible>)).parse_program()
 }
 
-impl Parser {
-    fn new(tokens: Vec<Token>) -> Parser {
+impl<I, E> Parser<I>
+where
+    I: Iterator<Item = std::result::Result<Spanned<Token>, E>>,
+    ParseError: From<E>,
+{
+    /**
+     * Create a parser consuming the tokens lazily
+     * An error of the token stream ends the parsing, and is reported as a parse error
+     */
+    pub fn new(tokens: I) -> Parser<I> {
         Parser {
             tokens,
-            cursor: 0
+            lookahead: VecDeque::new(),
+            previous: Span::default(),
+            exhausted: false,
+            failed: false,
+            next_id: 0,
+            errors: Vec::new(),
         }
     }
 
-    pub fn parse(tokens: Vec<Token>) {
-        Self::new(tokens).parse_program();
+    /**
+     * Parse all remaining tokens as a program
+     * See parse_tokens()
+     */
+    pub fn parse_program(mut self) -> std::result::Result<Program, Vec<ParseError>> {
+        let mut program = Program::default();
+
+        while !self.is_eof() {
+            if let Err(error) = self.parse_definition(&mut program) {
+                self.report(error);
+                self.synchronize();
+
+                // there is no block to close at the top level
+                if self.lookahead.front().is_some_and(|token| token.value == Token::ClosingBrace) {
+                    self.skip();
+                }
+            }
+        }
+
+        if self.errors.is_empty() {
+            Ok(program)
+        } else {
+            Err(self.errors)
+        }
     }
 
-    fn parse_program(&mut self) {
-        match self.next() {
-            Token::Symbol(name) => {
-                match self.current() {
-                    Token::OpeningParenthesis => self.parse_function(name),
-                    _ => self.parse_global(name),
+    fn parse_definition(&mut self, program: &mut Program) -> Result<()> {
+        let start = self.current_span();
+
+        let name = match self.next()? {
+            Token::Symbol(name) => name,
+            token => return Err(self.unexpected(token, "symbol")),
+        };
+
+        let previous = program.definition_span(name.as_str());
+
+        if let Some(previous) = previous {
+            // the definition is still parsed to check its syntax
+            self.report(
+                ParseError::new(format!("Duplicate definition of `{}`", name), start)
+                    .with_related(String::from("previously defined"), previous)
+            );
+        }
+
+        match self.current()? {
+            Token::OpeningParenthesis => {
+                let function = self.parse_function(name, start)?;
+
+                if previous.is_none() {
+                    program.functions.insert(function.name.to_string(), function);
+                }
+            },
+            _ => {
+                let variable = self.parse_global(name, start)?;
+
+                if previous.is_none() {
+                    program.globals.insert(variable.name().to_string(), variable);
                 }
             },
-            _ => panic!("Expecting symbol")
         }
+
+        Ok(())
     }
 
-    fn parse_function(&mut self, name: String) {
-        match self.next() {
-            Token::OpeningParenthesis => {},
-            _ => panic!("Expecting (")
-        }
+    fn parse_function(&mut self, name: Symbol, start: Span) -> Result<Function> {
+        self.check(Token::OpeningParenthesis)?;
 
-        let mut arguments: Vec<String> = Vec::new();
+        let mut arguments: Vec<Spanned<Symbol>> = Vec::new();
 
         loop {
-            match &self.next() {
+            match self.next()? {
                 Token::ClosingParenthesis => break,
                 Token::Symbol(name) => {
-                    arguments.push(String::from(name));
+                    arguments.push(Spanned::new(name, self.previous_span()));
 
-                    match &self.next() {
+                    match self.next()? {
                         Token::ClosingParenthesis => break,
-                        token if token.is_operator(',') => continue,
-                        _ => panic!("Invalid token")
+                        token if token.is_operator(Operator::Comma) => continue,
+                        token => return Err(self.unexpected(token, "`,` or `)`"))
                     }
                 },
-                _ => panic!("Invalid token")
+                token => return Err(self.unexpected(token, "argument name or `)`"))
             }
         }
 
-        self.parse_statement();
+        let body = self.parse_statement()?;
+
+        Ok(Function {
+            name,
+            arguments,
+            body,
+            statements: Vec::new(),
+            span: self.span_from(start),
+        })
     }
 
-    fn parse_statement(&mut self) {
-        match self.next() {
-            Token::Auto => self.parse_auto(),
-            Token::Extern => self.parse_extern(),
-            Token::Symbol(_) if self.current().is_operator(':') => println!("label"),
-            Token::Case => println!("case"),
-            Token::OpeningBrace => self.parse_statements(),
-            Token::If => self.parse_if(),
-            Token::While => println!("while"),
-            Token::Switch => println!("switch"),
-            Token::Goto => println!("goto"),
-            Token::Return => println!("return"),
-            _ => {
-                self.cursor = self.cursor - 1; // Rollback to previous token
-                println!("expression {:?}", self.parse_rvalue());
-                self.check(Token::EndOfStatement);
+    fn parse_statement(&mut self) -> Result<SpannedStatement> {
+        let start = self.current_span();
+
+        let statement = match self.next()? {
+            Token::Auto => self.parse_auto()?,
+            Token::Extern => self.parse_extern()?,
+            Token::Symbol(name) if self.current()?.is_operator(Operator::Colon) => self.parse_label(name)?,
+            Token::Case => self.parse_case()?,
+            Token::OpeningBrace => self.parse_statements()?,
+            Token::If => self.parse_if()?,
+            Token::While => self.parse_while()?,
+            Token::Switch => self.parse_switch()?,
+            Token::Goto => self.parse_goto()?,
+            Token::Return => self.parse_return()?,
+            Token::EndOfStatement => Statement::Null,
+            token => {
+                self.rollback(token);
+                let expression = self.parse_rvalue()?;
+                self.check(Token::EndOfStatement)?;
+
+                Statement::Expression(expression)
             }
-        }
+        };
+
+        Ok(Spanned::new(statement, self.span_from(start)))
     }
 
-    fn parse_statements(&mut self) {
-        println!("statements");
+    fn parse_statements(&mut self) -> Result<Statement> {
+        let mut statements = Vec::new();
 
         loop {
-            match self.current() {
+            match self.current()? {
                 Token::ClosingBrace => {
-                    self.next();
+                    self.next()?;
                     break
                 },
-                _ => self.parse_statement(),
+                _ => match self.parse_statement() {
+                    Ok(statement) => statements.push(statement),
+                    Err(error) => {
+                        self.report(error);
+                        self.synchronize();
+                    }
+                },
             }
         }
+
+        Ok(Statement::Block(statements))
     }
 
-    fn parse_auto(&mut self) {
-        match self.next() {
-            Token::Symbol(name) => {
-                println!("var {}", name);
+    fn parse_auto(&mut self) -> Result<Statement> {
+        let mut variables = Vec::new();
 
-                match self.next() {
-                    Token::Integer(value) => {
-                        println!("var {} = {}", name, value);
-                        match self.next() {
-                            Token::Operator(op) if op == "," => self.parse_auto(),
-                            Token::EndOfStatement => return,
-                            t => panic!("invalid token {:?}", t)
-                        }
-                    },
-                    Token::Operator(op) if op == "," => self.parse_auto(),
-                    Token::EndOfStatement => return,
-                    t => panic!("invalid token {:?}", t)
-                }
-            },
-            token => panic!("expecting symbol {:?}", token)
+        loop {
+            let name = match self.next()? {
+                Token::Symbol(name) => Spanned::new(name, self.previous_span()),
+                token => return Err(self.unexpected(token, "symbol"))
+            };
+
+            let size = match self.current()? {
+                Token::Integer(value) => {
+                    self.next()?;
+                    Some(value)
+                },
+                _ => None
+            };
+
+            variables.push(AutoVariable { name, size });
+
+            match self.next()? {
+                Token::Operator(Operator::Comma) => {},
+                Token::EndOfStatement => break,
+                token => return Err(self.unexpected(token, "`,` or `;`"))
+            }
         }
+
+        Ok(Statement::Auto(variables))
     }
 
-    fn parse_extern(&mut self) {
+    fn parse_extern(&mut self) -> Result<Statement> {
+        let mut names = Vec::new();
+
         loop {
-            match self.next() {
-                Token::Symbol(name) => println!("extern {}", name),
-                Token::Operator(op) if op == "," => {},
-                Token::EndOfStatement => return,
-                token => panic!("invalid token {:?}", token)
+            match self.next()? {
+                Token::Symbol(name) => names.push(Spanned::new(name, self.previous_span())),
+                Token::Operator(Operator::Comma) => {},
+                Token::EndOfStatement => break,
+                token => return Err(self.unexpected(token, "symbol, `,` or `;`"))
             }
         }
+
+        Ok(Statement::Extern(names))
     }
 
-    fn parse_if(&mut self) {
-        match self.next() {
-            Token::OpeningParenthesis => {},
-            token => panic!("invalid token {:?}", token)
+    fn parse_if(&mut self) -> Result<Statement> {
+        let condition = self.parse_parenthesized_rvalue()?;
+        let then = self.parse_statement()?;
+
+        // the else is always bound to the nearest if
+        let otherwise = if !self.is_eof() && self.current()? == Token::Else {
+            self.next()?;
+
+            Some(Box::new(self.parse_statement()?))
+        } else {
+            None
+        };
+
+        Ok(Statement::If {
+            condition,
+            then: Box::new(then),
+            otherwise,
+        })
+    }
+
+    fn parse_while(&mut self) -> Result<Statement> {
+        let condition = self.parse_parenthesized_rvalue()?;
+        let body = self.parse_statement()?;
+
+        Ok(Statement::While {
+            condition,
+            body: Box::new(body),
+        })
+    }
+
+    fn parse_switch(&mut self) -> Result<Statement> {
+        let value = self.parse_parenthesized_rvalue()?;
+        let body = self.parse_statement()?;
+
+        Ok(Statement::Switch {
+            value,
+            body: Box::new(body),
+        })
+    }
+
+    fn parse_case(&mut self) -> Result<Statement> {
+        let start = self.current_span();
+
+        let value = match self.next()? {
+            Token::Integer(value) => Expression::Integer(value),
+            Token::Char(chars) => Expression::Char(chars),
+            token => return Err(self.unexpected(token, "integer or char constant")),
+        };
+
+        let value = Spanned::new(value, start);
+
+        match self.next()? {
+            token if token.is_operator(Operator::Colon) => {},
+            token => return Err(self.unexpected(token, "`:`")),
         }
 
-        println!("if (");
+        let statement = self.parse_statement()?;
+
+        Ok(Statement::Case {
+            value,
+            statement: Box::new(statement),
+        })
+    }
+
+    /**
+     * Parse a labeled statement. The name is already consumed, and the current token is ":"
+     */
+    fn parse_label(&mut self, name: Symbol) -> Result<Statement> {
+        let name = Spanned::new(name, self.previous_span());
+
+        self.next()?;
+
+        let statement = self.parse_statement()?;
+
+        Ok(Statement::Label {
+            name,
+            statement: Box::new(statement),
+        })
+    }
 
-        println!("cond: {:?}", self.parse_rvalue());
+    fn parse_goto(&mut self) -> Result<Statement> {
+        let target = self.parse_rvalue()?;
 
-        match self.next() {
-            Token::ClosingParenthesis => {},
-            token => panic!("if: invalid token {:?} expecting )", token)
+        self.check(Token::EndOfStatement)?;
+
+        Ok(Statement::Goto(target))
+    }
+
+    fn parse_return(&mut self) -> Result<Statement> {
+        if self.current()? == Token::EndOfStatement {
+            self.next()?;
+
+            return Ok(Statement::Return(None));
         }
 
-        println!(") then");
+        let value = self.parse_parenthesized_rvalue()?;
 
-        self.parse_statement();
+        self.check(Token::EndOfStatement)?;
 
-        println!("endif")
+        Ok(Statement::Return(Some(value)))
     }
 
-    fn parse_rvalue(&mut self) -> String {
-        return self.parse_assign_left().join(" ") + self.parse_or_expr().as_str();
+    /**
+     * Parse an rvalue wrapped into parenthesis, like conditions of if or while statements
+     * The span of the returned expression excludes the parenthesis
+     */
+    fn parse_parenthesized_rvalue(&mut self) -> Result<SpannedExpression> {
+        self.check(Token::OpeningParenthesis)?;
+
+        let expression = self.parse_rvalue()?;
+
+        self.check(Token::ClosingParenthesis)?;
+
+        Ok(expression)
+    }
+
+    fn parse_rvalue(&mut self) -> Result<SpannedExpression> {
+        let expr = self.parse_conditional_expr()?;
+
+        if !self.current()?.is_assignment() {
+            return Ok(expr);
+        }
+
+        // assignment is right associative
+        let operator = match self.next()? {
+            Token::Operator(operator) => operator.compound().and_then(BinaryOperator::from_operator),
+            _ => unreachable!(),
+        };
+
+        let value = self.parse_rvalue()?;
+
+        Ok(Self::binary_node(expr, value, |target, value| Expression::Assign {
+            operator,
+            target,
+            value,
+        }))
     }
 
-    fn parse_or_expr(&mut self) -> String {
-        let mut expr = self.parse_and_expr();
+    fn parse_conditional_expr(&mut self) -> Result<SpannedExpression> {
+        let condition = self.parse_or_expr()?;
+
+        if !self.current()?.is_operator(Operator::Question) {
+            return Ok(condition);
+        }
+
+        self.next()?;
 
-        while self.current().is_operator_str("||") {
-            self.next();
+        let then = self.parse_rvalue()?;
 
-            expr += ", ";
-            expr += self.parse_and_expr().as_str();
+        match self.next()? {
+            token if token.is_operator(Operator::Colon) => {},
+            token => return Err(self.unexpected(token, "`:`")),
         }
 
-        return format!("and({})", expr);
+        // conditional is right associative
+        let otherwise = self.parse_conditional_expr()?;
+        let span = condition.span.merge(otherwise.span);
+
+        Ok(Spanned::new(
+            Expression::Conditional {
+                condition: Box::new(condition),
+                then: Box::new(then),
+                otherwise: Box::new(otherwise),
+            },
+            span
+        ))
     }
 
-    fn parse_and_expr(&mut self) -> String {
-        let mut expr = self.parse_equality_expr();
+    fn parse_or_expr(&mut self) -> Result<SpannedExpression> {
+        let mut expr = self.parse_and_expr()?;
 
-        while self.current().is_operator_str("&&") {
-            self.next();
+        while self.current()?.is_operator(Operator::Pipe) {
+            self.next()?;
 
-            expr += ", ";
-            expr += self.parse_equality_expr().as_str();
+            expr = Self::binary(BinaryOperator::Or, expr, self.parse_and_expr()?);
         }
 
-        return format!("or({})", expr);
+        Ok(expr)
     }
 
-    fn parse_equality_expr(&mut self) -> String {
-        let mut expr = self.parse_shift_expr();
+    fn parse_and_expr(&mut self) -> Result<SpannedExpression> {
+        let mut expr = self.parse_equality_expr()?;
 
-        loop {
-            match self.current() {
-                Token::Operator(op) if op == "==" => {
-                    self.next();
-                    expr = format!("equals({}, {})", expr, self.parse_shift_expr());
-                },
-                Token::Operator(op) if op == "!=" => {
-                    self.next();
-                    expr = format!("different({}, {})", expr, self.parse_shift_expr());
-                },
-                _ => break
-            }
+        while self.current()?.is_operator(Operator::Ampersand) {
+            self.next()?;
+
+            expr = Self::binary(BinaryOperator::And, expr, self.parse_equality_expr()?);
         }
 
-        return expr;
+        Ok(expr)
     }
 
-    fn parse_shift_expr(&mut self) -> String {
-        let mut expr = self.parse_add_expr();
+    fn parse_equality_expr(&mut self) -> Result<SpannedExpression> {
+        let mut expr = self.parse_relational_expr()?;
 
         loop {
-            match self.current() {
-                Token::Operator(op) if op == ">>" => {
-                    self.next();
-                    expr = format!("shift_right({}, {})", expr, self.parse_add_expr());
+            match self.current()? {
+                Token::Operator(Operator::Equal) => {
+                    self.next()?;
+                    expr = Self::binary(BinaryOperator::Equal, expr, self.parse_relational_expr()?);
                 },
-                Token::Operator(op) if op == "<<" => {
-                    self.next();
-                    expr = format!("shift_left({}, {})", expr, self.parse_add_expr());
+                Token::Operator(Operator::NotEqual) => {
+                    self.next()?;
+                    expr = Self::binary(BinaryOperator::NotEqual, expr, self.parse_relational_expr()?);
                 },
                 _ => break
             }
         }
 
-        return expr;
+        Ok(expr)
     }
 
-    fn parse_add_expr(&mut self) -> String {
-        let mut expr = self.parse_mul_expr();
+    fn parse_relational_expr(&mut self) -> Result<SpannedExpression> {
+        let mut expr = self.parse_shift_expr()?;
 
         loop {
-            match self.current() {
-                Token::Operator(op) if op == "+" => {
-                    self.next();
-                    expr = format!("add({}, {})", expr, self.parse_mul_expr());
-                },
-                Token::Operator(op) if op == "-" => {
-                    self.next();
-                    expr = format!("minus({}, {})", expr, self.parse_mul_expr());
-                },
+            let operator = match self.current()? {
+                Token::Operator(Operator::Less) => BinaryOperator::Less,
+                Token::Operator(Operator::LessEqual) => BinaryOperator::LessEqual,
+                Token::Operator(Operator::Greater) => BinaryOperator::Greater,
+                Token::Operator(Operator::GreaterEqual) => BinaryOperator::GreaterEqual,
                 _ => break
-            }
+            };
+
+            self.next()?;
+            expr = Self::binary(operator, expr, self.parse_shift_expr()?);
         }
 
-        return expr;
+        Ok(expr)
     }
 
-    fn parse_mul_expr(&mut self) -> String {
-        let mut expr = self.parse_prefix_expr();
+    fn parse_shift_expr(&mut self) -> Result<SpannedExpression> {
+        let mut expr = self.parse_add_expr()?;
 
         loop {
-            match self.current() {
-                Token::Operator(op) if op == "*" => {
-                    self.next();
-                    expr = format!("multiply({}, {})", expr, self.parse_prefix_expr());
+            match self.current()? {
+                Token::Operator(Operator::ShiftRight) => {
+                    self.next()?;
+                    expr = Self::binary(BinaryOperator::ShiftRight, expr, self.parse_add_expr()?);
                 },
-                Token::Operator(op) if op == "/" => {
-                    self.next();
-                    expr = format!("divide({}, {})", expr, self.parse_prefix_expr());
-                },
-                Token::Operator(op) if op == "%" => {
-                    self.next();
-                    expr = format!("mod({}, {})", expr, self.parse_prefix_expr());
+                Token::Operator(Operator::ShiftLeft) => {
+                    self.next()?;
+                    expr = Self::binary(BinaryOperator::ShiftLeft, expr, self.parse_add_expr()?);
                 },
                 _ => break
             }
         }
 
-        return expr;
+        Ok(expr)
     }
 
-    fn parse_assign_left(&mut self) -> Vec<String> {
-        let mut ret = Vec::new();
+    fn parse_add_expr(&mut self) -> Result<SpannedExpression> {
+        let mut expr = self.parse_mul_expr()?;
 
         loop {
-            let cursor = self.cursor;
-            let left = self.parse_prefix_expr();
-
-            if !self.current().is_operator('=') {
-                self.cursor = cursor; // rollback to position before parsing
-                break;
-            }
-
-            match self.next() {
-                Token::Operator(op) if (op == "|" || op == "&" || op == "==" || op == "!=" || op == "<" || op == "<=" || op == ">" || op == ">=" || op == ">>" || op == "<<" || op == "-" || op == "+" || op == "%" || op == "*" || op == "/") => {
-                    ret.push(format!("{} {}= ", left, op))
+            match self.current()? {
+                Token::Operator(Operator::Plus) => {
+                    self.next()?;
+                    expr = Self::binary(BinaryOperator::Add, expr, self.parse_mul_expr()?);
                 },
-                _ => {
-                    ret.push(format!("{} = ", left))
-                }
+                Token::Operator(Operator::Minus) => {
+                    self.next()?;
+                    expr = Self::binary(BinaryOperator::Subtract, expr, self.parse_mul_expr()?);
+                },
+                _ => break
             }
         }
 
-        return ret;
+        Ok(expr)
     }
 
-    fn parse_prefix_expr(&mut self) -> String {
-        let mut expr = String::new();
+    fn parse_mul_expr(&mut self) -> Result<SpannedExpression> {
+        let mut expr = self.parse_prefix_expr()?;
 
         loop {
-            match self.current() {
-                Token::Operator(op) if (op == "!" || op == "-" || op == "&" || op == "*" || op == "++" || op == "--") => {
-                    self.next();
-                    expr += format!("unary({}) ", op).as_str();
+            match self.current()? {
+                Token::Operator(Operator::Star) => {
+                    self.next()?;
+                    expr = Self::binary(BinaryOperator::Multiply, expr, self.parse_prefix_expr()?);
+                },
+                Token::Operator(Operator::Slash) => {
+                    self.next()?;
+                    expr = Self::binary(BinaryOperator::Divide, expr, self.parse_prefix_expr()?);
+                },
+                Token::Operator(Operator::Percent) => {
+                    self.next()?;
+                    expr = Self::binary(BinaryOperator::Modulo, expr, self.parse_prefix_expr()?);
                 },
                 _ => break
             }
         }
 
-        expr += self.parse_postfix_expr().as_str();
+        Ok(expr)
+    }
+
+    fn parse_prefix_expr(&mut self) -> Result<SpannedExpression> {
+        let start = self.current_span();
+
+        let operator = match self.current()? {
+            Token::Operator(Operator::Not) => UnaryOperator::Not,
+            Token::Operator(Operator::Minus) => UnaryOperator::Negate,
+            Token::Operator(Operator::Ampersand) => UnaryOperator::Address,
+            Token::Operator(Operator::Star) => UnaryOperator::Indirection,
+            Token::Operator(Operator::Increment) => UnaryOperator::PreIncrement,
+            Token::Operator(Operator::Decrement) => UnaryOperator::PreDecrement,
+            _ => return self.parse_postfix_expr(),
+        };
+
+        self.next()?;
 
-        return expr;
+        let operand = self.parse_prefix_expr()?;
+
+        Ok(Spanned::new(
+            Expression::Unary {
+                operator,
+                operand: Box::new(operand),
+            },
+            self.span_from(start)
+        ))
     }
 
-    fn parse_postfix_expr(&mut self) -> String {
-        let mut expr = self.parse_atomic_expr();
+    fn parse_postfix_expr(&mut self) -> Result<SpannedExpression> {
+        let start = self.current_span();
+        let mut expr = self.parse_atomic_expr()?;
 
         loop {
-            match self.current() {
-                Token::OpeningBracket => todo!("implements array access"),
+            match self.current()? {
+                Token::OpeningBracket => {
+                    self.next()?;
+
+                    let index = self.parse_rvalue()?;
+
+                    self.check(Token::ClosingBracket)?;
+
+                    expr = Spanned::new(
+                        Expression::Index {
+                            vector: Box::new(expr),
+                            index: Box::new(index),
+                        },
+                        self.span_from(start)
+                    );
+                },
                 Token::OpeningParenthesis => {
-                    expr = format!("call({}, [{}])", expr, self.parse_function_call_arguments().join(", "));
+                    let arguments = self.parse_function_call_arguments()?;
+
+                    expr = Spanned::new(
+                        Expression::Call {
+                            function: Box::new(expr),
+                            arguments,
+                        },
+                        self.span_from(start)
+                    );
                 },
-                Token::Operator(op) if (op == "++" || op == "--") => {
-                    self.next();
-
-                    expr = format!("postfix({}, {})", expr, op);
+                Token::Operator(op @ (Operator::Increment | Operator::Decrement)) => {
+                    self.next()?;
+
+                    let operator = if op == Operator::Increment { UnaryOperator::PostIncrement } else { UnaryOperator::PostDecrement };
+
+                    expr = Spanned::new(
+                        Expression::Unary {
+                            operator,
+                            operand: Box::new(expr),
+                        },
+                        self.span_from(start)
+                    );
                 },
                 _ => break,
             }
         }
 
-        return expr;
+        Ok(expr)
     }
 
-    fn parse_function_call_arguments(&mut self) -> Vec<String> {
-        self.check(Token::OpeningParenthesis);
+    fn parse_function_call_arguments(&mut self) -> Result<Vec<SpannedExpression>> {
+        self.check(Token::OpeningParenthesis)?;
 
         let mut args = Vec::new();
 
-        while self.current() != Token::ClosingParenthesis {
-            args.push(self.parse_rvalue());
+        while self.current()? != Token::ClosingParenthesis {
+            args.push(self.parse_rvalue()?);
 
-            match self.current() {
-                Token::Operator(op) if op == "," => {
-                    self.next();
+            match self.current()? {
+                Token::Operator(Operator::Comma) => {
+                    self.next()?;
                 },
                 Token::ClosingParenthesis => {},
-                tok => panic!("unexpected token {:?} expecting , or )", tok)
+                _ => {
+                    let token = self.next()?;
+                    return Err(self.unexpected(token, "`,` or `)`"));
+                }
             }
         }
 
-        self.next();
+        self.next()?;
 
-        return args;
+        Ok(args)
     }
 
-    fn parse_atomic_expr(&mut self) -> String {
-        match self.next() {
-            Token::Integer(ival) => format!("int({})", ival),
-            Token::String(str) => format!("string({})", str),
-            Token::Char(chars) => format!("char({})", chars[0]),
-            Token::Symbol(var) => format!("var({})", var),
+    fn parse_atomic_expr(&mut self) -> Result<SpannedExpression> {
+        let start = self.current_span();
+
+        let expr = match self.next()? {
+            Token::Integer(ival) => Expression::Integer(ival),
+            Token::String(str) => Expression::String(str),
+            Token::Char(chars) => Expression::Char(chars),
+            Token::Symbol(name) => Expression::Name { name, id: self.node_id() },
             Token::OpeningParenthesis => {
-                let expr = format!("({})", self.parse_rvalue());
+                let expr = self.parse_rvalue()?;
+
+                self.check(Token::ClosingParenthesis)?;
+
+                // keep the parenthesis into the expression span
+                return Ok(Spanned::new(expr.value, self.span_from(start)));
+            },
+            token => return Err(self.unexpected(token, "expression")),
+        };
+
+        Ok(Spanned::new(expr, start))
+    }
+
+    fn parse_global(&mut self, name: Symbol, start: Span) -> Result<Variable> {
+        let size = match self.current()? {
+            Token::OpeningBracket => {
+                self.next()?;
+
+                let size = match self.current()? {
+                    Token::ClosingBracket => None,
+                    Token::Integer(size) if size >= 0 => {
+                        self.next()?;
+                        Some(size as u32)
+                    },
+                    _ => {
+                        let token = self.next()?;
+                        return Err(self.unexpected(token, "vector size or `]`"));
+                    }
+                };
+
+                self.check(Token::ClosingBracket)?;
+
+                Some(size)
+            },
+            _ => None
+        };
+
+        let mut initial = Vec::new();
+
+        if self.current()? == Token::EndOfStatement {
+            self.next()?;
+        } else {
+            loop {
+                initial.push(self.parse_initial_value()?);
+
+                match self.next()? {
+                    Token::Operator(Operator::Comma) => {},
+                    Token::EndOfStatement => break,
+                    token => return Err(self.unexpected(token, "`,` or `;`"))
+                }
+            }
+        }
+
+        let span = self.span_from(start);
+
+        Ok(match size {
+            Some(size) => Variable::Array { name, size, initial, span },
+            None => Variable::Atomic { name, initial, span },
+        })
+    }
+
+    fn parse_initial_value(&mut self) -> Result<Value> {
+        match self.next()? {
+            Token::Integer(value) => Ok(Value::Constant(value)),
+            Token::Operator(Operator::Minus) => match self.next()? {
+                Token::Integer(value) => Ok(Value::Constant(value.wrapping_neg())),
+                token => Err(self.unexpected(token, "integer"))
+            },
+            Token::Char(chars) => Ok(Value::Char(chars)),
+            Token::String(str) => Ok(Value::String(str)),
+            Token::Symbol(name) => Ok(Value::Variable(name)),
+            token => Err(self.unexpected(token, "constant or symbol"))
+        }
+    }
+
+    /**
+     * Create a binary operation node
+     */
+    fn binary(operator: BinaryOperator, left: SpannedExpression, right: SpannedExpression) -> SpannedExpression {
+        Self::binary_node(left, right, |left, right| Expression::Binary {
+            operator,
+            left,
+            right,
+        })
+    }
 
-                self.check(Token::ClosingParenthesis);
+    /**
+     * Create a node with two operands, with a span covering both
+     */
+    fn binary_node<F>(left: SpannedExpression, right: SpannedExpression, factory: F) -> SpannedExpression
+        where F: FnOnce(Box<SpannedExpression>, Box<SpannedExpression>) -> Expression
+    {
+        let span = left.span.merge(right.span);
 
-                return expr;
+        Spanned::new(factory(Box::new(left), Box::new(right)), span)
+    }
+
+    /**
+     * Pull the next token from the iterator, if the lookahead is empty
+     */
+    fn fill(&mut self) {
+        if self.exhausted || !self.lookahead.is_empty() {
+            return;
+        }
+
+        match self.tokens.next() {
+            Some(Ok(token)) => self.lookahead.push_back(token),
+            Some(Err(error)) => {
+                self.report(error.into());
+                self.exhausted = true;
+                self.failed = true;
             },
-            tok => panic!("expr: Invalid token {:?} expecting int, string, char, symbol or (", tok),
+            None => self.exhausted = true,
+        }
+    }
+
+    fn is_eof(&mut self) -> bool {
+        self.fill();
+        self.lookahead.is_empty()
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        self.fill();
+
+        match self.skip() {
+            Some(token) => Ok(token),
+            None => Err(self.end_of_file()),
+        }
+    }
+
+    /**
+     * Put back the last consumed token, so it will be returned by the next call to next()
+     */
+    fn rollback(&mut self, token: Token) {
+        self.lookahead.push_front(Spanned::new(token, self.previous));
+    }
+
+    /**
+     * Consume the current token, if already pulled
+     */
+    fn skip(&mut self) -> Option<Token> {
+        let token = self.lookahead.pop_front()?;
+        self.previous = token.span;
+
+        Some(token.value)
+    }
+
+    fn current(&mut self) -> Result<Token> {
+        self.fill();
+
+        match self.lookahead.front() {
+            Some(token) => Ok(token.value.clone()),
+            None => Err(self.end_of_file()),
+        }
+    }
+
+    fn end_of_file(&self) -> ParseError {
+        ParseError::new(String::from("Unexpected end of file"), self.current_span_at_eof())
+    }
+
+    /**
+     * At the end of the file, the current location is an empty span after the last token
+     */
+    fn current_span_at_eof(&self) -> Span {
+        Span::new(self.previous.end, self.previous.end)
+    }
+
+    /**
+     * Get the source location of the current token
+     * At the end of the file, an empty span after the last token is returned
+     */
+    fn current_span(&mut self) -> Span {
+        self.fill();
+
+        match self.lookahead.front() {
+            Some(token) => token.span,
+            None => self.current_span_at_eof(),
         }
     }
 
-    fn parse_global(&mut self, name: String) {
-        todo!()
+    /**
+     * Get a new identifier for a name expression
+     */
+    fn node_id(&mut self) -> NodeId {
+        self.next_id += 1;
+
+        NodeId(self.next_id - 1)
     }
 
-    fn next(&mut self) -> Token {
-        let token = self.tokens[self.cursor].clone(); // @todo copy instead of clone
-        self.cursor += 1;
+    /**
+     * Get the source location of the last consumed token
+     */
+    fn previous_span(&self) -> Span {
+        self.previous
+    }
 
-        token
+    /**
+     * Get the span starting at the given one, and ending at the last consumed token
+     */
+    fn span_from(&self, start: Span) -> Span {
+        start.merge(self.previous_span())
     }
 
-    fn current(&self) -> Token {
-        self.tokens[self.cursor].clone()
+    /**
+     * Create the error for the last consumed token
+     * A ";" or a "}" is put back, so the error recovery does not skip the following statement
+     */
+    fn unexpected(&mut self, token: Token, expecting: &str) -> ParseError {
+        let error = ParseError::unexpected(format!("`{}`", token), String::from(expecting), self.previous_span());
+
+        if matches!(token, Token::EndOfStatement | Token::ClosingBrace) {
+            self.rollback(token);
+        }
+
+        error
     }
 
-    fn check(&mut self, expecting: Token) {
-        let token = self.next();
+    fn check(&mut self, expecting: Token) -> Result<()> {
+        let token = self.next()?;
 
         if token != expecting {
-            panic!("invalid token {:?} expecting {:?}", token, expecting);
+            return Err(self.unexpected(token, format!("`{}`", expecting).as_str()));
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Store the error, and continue parsing
+     * The same error is reported only once (i.e. end of file error reported by multiple statements)
+     */
+    fn report(&mut self, error: ParseError) {
+        if !self.failed && self.errors.last() != Some(&error) {
+            self.errors.push(error);
+        }
+    }
+
+    /**
+     * Skip tokens after an error, until the end of the current statement
+     *
+     * Stops after a ";", or after a "}" closing a block opened while skipping.
+     * A "}" closing the enclosing block is not consumed, so the block can be terminated.
+     */
+    fn synchronize(&mut self) {
+        let mut depth = 0;
+
+        while !self.is_eof() {
+            match self.lookahead[0].value {
+                Token::EndOfStatement if depth == 0 => {
+                    self.skip();
+                    return;
+                },
+                Token::OpeningBrace => depth += 1,
+                Token::ClosingBrace if depth == 0 => return,
+                Token::ClosingBrace => {
+                    depth -= 1;
+
+                    if depth == 0 {
+                        self.skip();
+                        return;
+                    }
+                },
+                _ => {}
+            }
+
+            self.skip();
         }
     }
 }
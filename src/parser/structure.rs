@@ -1,32 +1,132 @@
-use std::iter::Map;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
 
+use crate::parser::ast::SpannedStatement;
 use crate::parser::Opcode;
+use crate::tokenizer::{quote, Span, Spanned, Symbol, Token};
 
+/**
+ * The parsed program : all top-level definitions indexed by name
+ */
+#[derive(Debug, Default)]
 pub struct Program {
-    globals: Map<String, Variable>,
-    functions: Map<String, Function>,
+    pub globals: BTreeMap<String, Variable>,
+    pub functions: BTreeMap<String, Function>,
 }
 
+impl Program {
+    /**
+     * Get the location of the global or function definition with the given name
+     */
+    pub fn definition_span(&self, name: &str) -> Option<Span> {
+        self.functions.get(name).map(|function| function.span)
+            .or_else(|| self.globals.get(name).map(|variable| variable.span()))
+    }
+}
+
+/**
+ * A global variable definition
+ */
+#[derive(Debug, Clone, PartialEq)]
 pub enum Variable {
+    /**
+     * A scalar : x;  or  x 1;
+     * When multiple initial values are given, they are stored in consecutive words : x 1, 2, 3;
+     */
     Atomic {
-        name: String,
-        initial: Option<Value>
+        name: Symbol,
+        initial: Vec<Value>,
+        span: Span,
     },
+    /**
+     * A vector : v[10];  or  v[] 1, 2, 3;
+     * The size may be omitted, in this case it is deduced from the initial values
+     */
     Array {
-        name: String,
-        size: u32,
-        initial: Vec<Value>
+        name: Symbol,
+        size: Option<u32>,
+        initial: Vec<Value>,
+        span: Span,
     },
 }
 
+impl Variable {
+    pub fn name(&self) -> Symbol {
+        match self {
+            Variable::Atomic { name, .. } => *name,
+            Variable::Array { name, .. } => *name,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Variable::Atomic { span, .. } => *span,
+            Variable::Array { span, .. } => *span,
+        }
+    }
+
+    pub fn initial(&self) -> &[Value] {
+        match self {
+            Variable::Atomic { initial, .. } => initial,
+            Variable::Array { initial, .. } => initial,
+        }
+    }
+
+    /**
+     * Get the number of words allocated for the vector
+     * It's the greatest value between the declared size and the number of initial values
+     * Returns None for a scalar
+     */
+    pub fn vector_size(&self) -> Option<u32> {
+        match self {
+            Variable::Atomic { .. } => None,
+            Variable::Array { size, initial, .. } => Some(size.unwrap_or(0).max(initial.len() as u32)),
+        }
+    }
+}
+
+/**
+ * An initial value of a global variable
+ */
+#[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     Constant(i32),
-    Variable(String),
+    Char([char; 4]),
+    String(String),
+    /**
+     * The address of another global or function
+     */
+    Variable(Symbol),
+}
+
+impl Display for Value {
+    /**
+     * Display the value as written into the source code
+     */
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Constant(value) => write!(f, "{}", value),
+            Value::Char(chars) => write!(f, "{}", Token::Char(*chars)),
+            Value::String(value) => write!(f, "{}", quote(value.chars(), '"')),
+            Value::Variable(name) => write!(f, "{}", name),
+        }
+    }
 }
 
+#[derive(Debug)]
 pub struct Function {
-    name: String,
-    arguments: Vec<String>,
-    // @todo parse local var and extern names ?
-    statements: Vec<Opcode>,
+    pub name: Symbol,
+    pub arguments: Vec<Spanned<Symbol>>,
+    /**
+     * The function body, as parsed
+     */
+    pub body: SpannedStatement,
+    /**
+     * The body translated into opcodes, filled by the lowering
+     */
+    pub statements: Vec<Opcode>,
+    /**
+     * Location of the whole function definition
+     */
+    pub span: Span,
 }
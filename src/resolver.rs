@@ -0,0 +1,138 @@
+pub use error::ResolveError;
+pub use resolver::Resolver;
+pub use scope::{Binding, FunctionScope, Resolution};
+
+mod error;
+mod scope;
+#[allow(clippy::module_inception)]
+mod resolver;
+
+#[cfg(test)]
+mod test {
+    use crate::layout::WordSize;
+    use crate::parser::ast::{Expression, NodeId, SpannedExpression, Statement};
+    use crate::resolver::{Binding, ResolveError, Resolver};
+    use crate::tokenizer::{Position, Span, Symbol};
+
+    fn resolve_errors(source: &str) -> Vec<ResolveError> {
+        Resolver::new().resolve(&crate::parse(source).unwrap()).unwrap_err()
+    }
+
+    fn messages(errors: &[ResolveError]) -> Vec<&str> {
+        errors.iter().map(|error| error.message()).collect()
+    }
+
+    fn span(start: usize, end: usize) -> Span {
+        Span::new(Position::new(start, 1, start + 1), Position::new(end, 1, end + 1))
+    }
+
+    #[test]
+    fn resolve_printn() {
+        let (_, resolution) = crate::resolve("printn(n, b) { extrn putchar; auto a; if (a = n / b) printn(a, b); putchar(n % b + '0'); }").unwrap();
+        let scope = resolution.scope("printn").unwrap();
+
+        assert_eq!(vec![Symbol::intern("n"), Symbol::intern("b")], scope.parameters.iter().map(|p| p.value).collect::<Vec<_>>());
+        assert_eq!(Symbol::intern("a"), scope.autos[0].name.value);
+        assert_eq!(Symbol::intern("putchar"), scope.externs[0].value);
+        assert!(scope.labels.is_empty());
+
+        assert_eq!(Some(Binding::Parameter(1)), scope.lookup(Symbol::intern("b")));
+        // the name expressions are numbered in the source order
+        assert_eq!(Some(Binding::Auto(0)), scope.binding(NodeId(0)));
+        assert_eq!(Some(Binding::Parameter(0)), scope.binding(NodeId(1)));
+        assert_eq!(Some(Binding::Global(Symbol::intern("printn"))), scope.binding(NodeId(3)));
+        assert_eq!(Some(Binding::Global(Symbol::intern("putchar"))), scope.binding(NodeId(6)));
+        assert_eq!(Some(span(21, 28)), scope.declaration_span(Binding::Global(Symbol::intern("putchar"))));
+        assert_eq!(None, scope.declaration_span(Binding::Global(Symbol::intern("printn"))));
+    }
+
+    #[test]
+    fn resolve_labels() {
+        let (program, resolution) = crate::resolve("f() { goto end; loop: { auto x; x = 1; goto loop; } end: return; }").unwrap();
+        let scope = resolution.scope("f").unwrap();
+        let Statement::Block(statements) = &program.functions["f"].body.value else {
+            panic!("The body should be a block");
+        };
+        let Statement::Goto(SpannedExpression { value: Expression::Name { id, .. }, .. }) = statements[0].value else {
+            panic!("The first statement should be a goto");
+        };
+
+        assert_eq!(Some(Binding::Label(1)), scope.binding(id));
+        assert_eq!(Some(Binding::Label(0)), scope.lookup(Symbol::intern("loop")));
+        assert_eq!(Some(Binding::Auto(0)), scope.lookup(Symbol::intern("x")));
+    }
+
+    #[test]
+    fn resolve_globals() {
+        let (_, resolution) = crate::resolve("v[] 1, p; p; main() { extrn v, p; v[0] = &p; }").unwrap();
+
+        assert_eq!(Some(Binding::Global(Symbol::intern("p"))), resolution.scope("main").unwrap().lookup(Symbol::intern("p")));
+        assert_eq!(
+            vec!["Undefined name `q`"],
+            messages(&resolve_errors("v[] 1, q; main() {}"))
+        );
+    }
+
+    #[test]
+    fn resolve_errors_in_order() {
+        let errors = resolve_errors("f(a, a) { extrn g, h; auto x, g; x = y; z(1); l: l: goto m; }\ng;");
+
+        assert_eq!(
+            vec![
+                "Duplicate declaration of `a`",
+                "Undefined external name `h`",
+                "Duplicate declaration of `g`",
+                "Undeclared name `y`",
+                "Undefined function `z`",
+                "Duplicate declaration of `l`",
+                "Undeclared name `m`",
+            ],
+            messages(&errors)
+        );
+        assert_eq!(Some(("previously declared", span(2, 3))), errors[0].related());
+        assert_eq!(span(5, 6), errors[0].span());
+        assert_eq!(Some(("previously declared", span(16, 17))), errors[2].related());
+    }
+
+    #[test]
+    fn resolve_lvalues_and_cases() {
+        let errors = resolve_errors("f(a) { l: a = 1; 1 = a; &l; ++(a + 1); *a = v[1] = a[2]++; case 1: switch (a) { case 2: ; } }\nv;");
+
+        assert_eq!(
+            vec![
+                "Expecting an lvalue",
+                "Expecting an lvalue",
+                "Expecting an lvalue",
+                "Undeclared name `v`",
+                "Case outside of a switch",
+            ],
+            messages(&errors)
+        );
+        assert_eq!(span(17, 18), errors[0].span());
+        assert_eq!(span(30, 37), errors[2].span());
+    }
+
+    #[test]
+    fn resolve_char_constants() {
+        let source = "v 'abc'; f() { switch (1) case 'ab': return ('é'); return ('éa'); }";
+        let errors = Resolver::new().with_word_size(WordSize::Bits16).resolve(&crate::parse(source).unwrap()).unwrap_err();
+
+        assert_eq!(
+            vec![
+                "The char constant 'abc' does not fit into a 16 bits word",
+                "The char constant 'éa' does not fit into a 16 bits word",
+            ],
+            messages(&errors)
+        );
+        assert_eq!(Span::new(Position::new(60, 1, 60), Position::new(65, 1, 64)), errors[1].span());
+        assert!(Resolver::new().with_word_size(WordSize::Bits32).resolve(&crate::parse(source).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn resolve_externals() {
+        let source = "main() { extrn putchar; putchar('a'); }";
+
+        assert_eq!(vec!["Undefined external name `putchar`"], messages(&resolve_errors(source)));
+        assert!(Resolver::new().with_externals(["putchar"]).resolve(&crate::parse(source).unwrap()).is_ok());
+    }
+}
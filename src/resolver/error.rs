@@ -0,0 +1,64 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use crate::tokenizer::Span;
+
+/**
+ * Error raised when a name cannot be bound to its declaration
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolveError {
+    message: String,
+    span: Span,
+    /**
+     * Another location of the source related to the error, with its description
+     * For example the first declaration of a duplicate name
+     */
+    related: Option<Box<(String, Span)>>,
+}
+
+impl ResolveError {
+    pub fn new(message: String, span: Span) -> ResolveError {
+        ResolveError {
+            message,
+            span,
+            related: None,
+        }
+    }
+
+    pub fn with_related(mut self, description: String, span: Span) -> ResolveError {
+        self.related = Some(Box::new((description, span)));
+        self
+    }
+
+    pub fn message(&self) -> &str {
+        self.message.as_str()
+    }
+
+    /**
+     * The location of the error
+     */
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn related(&self) -> Option<(&str, Span)> {
+        self.related.as_deref().map(|(description, span)| (description.as_str(), *span))
+    }
+}
+
+impl Display for ResolveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ResolveError: {} at {}", self.message, self.span)?;
+
+        if let Some((description, span)) = self.related.as_deref() {
+            write!(f, " ({} at {})", description, span)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for ResolveError {
+
+}
@@ -0,0 +1,293 @@
+use std::collections::HashSet;
+
+use crate::parser::ast::{Expression, SpannedExpression, SpannedStatement, Statement, UnaryOperator};
+use crate::layout::WordSize;
+use crate::parser::{Function, Program, Value};
+use crate::resolver::{Binding, FunctionScope, Resolution, ResolveError};
+use crate::tokenizer::{Span, Spanned, Symbol, Token};
+
+/**
+ * Bind the names used by the functions to their declarations
+ *
+ * Names are declared by the function arguments, auto, extrn and labels.
+ * A name may be used before its declaration, but must be declared only once per function.
+ * An undeclared name can only be used to call a global function.
+ *
+ * The resolver also checks the operands requiring an address, the location of the case statements,
+ * and that the char constants fit into a word.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Resolver {
+    /**
+     * Names defined outside of the program, like the runtime library
+     */
+    externals: HashSet<Symbol>,
+    /**
+     * The word size of the machine running the program
+     */
+    word_size: WordSize,
+}
+
+impl Resolver {
+    pub fn new() -> Resolver {
+        Resolver::default()
+    }
+
+    /**
+     * Add names which can be declared by extrn without being defined by the program
+     */
+    pub fn with_externals<'a, I: IntoIterator<Item = &'a str>>(mut self, names: I) -> Resolver {
+        self.externals.extend(names.into_iter().map(Symbol::intern));
+        self
+    }
+
+    /**
+     * Set the word size the char constants must fit into, the default one by default
+     */
+    pub fn with_word_size(mut self, word_size: WordSize) -> Resolver {
+        self.word_size = word_size;
+        self
+    }
+
+    /**
+     * Resolve the names of all functions, and get their scopes
+     * The errors are sorted by location
+     */
+    pub fn resolve(&self, program: &Program) -> Result<Resolution, Vec<ResolveError>> {
+        let defined: HashSet<Symbol> = program.globals.values().map(|variable| variable.name())
+            .chain(program.functions.values().map(|function| function.name))
+            .chain(self.externals.iter().copied())
+            .collect();
+
+        let mut errors = Vec::new();
+        let mut resolution = Resolution::default();
+
+        for variable in program.globals.values() {
+            for value in variable.initial() {
+                match value {
+                    Value::Variable(name) if !defined.contains(name) => {
+                        errors.push(ResolveError::new(format!("Undefined name `{}`", name), variable.span()));
+                    },
+                    Value::Char(chars) if !self.word_size.fits_chars(chars) => {
+                        errors.push(char_error(chars, self.word_size, variable.span()));
+                    },
+                    _ => {},
+                }
+            }
+        }
+
+        for (name, function) in &program.functions {
+            let mut resolver = FunctionResolver {
+                scope: FunctionScope::default(),
+                defined: &defined,
+                word_size: self.word_size,
+                switches: 0,
+                errors: &mut errors,
+            };
+
+            resolver.resolve(function);
+            resolution.scopes.insert(name.clone(), resolver.scope);
+        }
+
+        if errors.is_empty() {
+            Ok(resolution)
+        } else {
+            errors.sort_by_key(|error| error.span().start.offset);
+            Err(errors)
+        }
+    }
+}
+
+/**
+ * Build the scope of a single function
+ */
+struct FunctionResolver<'a> {
+    scope: FunctionScope,
+    /**
+     * Names of the globals, functions and externals
+     */
+    defined: &'a HashSet<Symbol>,
+    word_size: WordSize,
+    /**
+     * Number of switch statements enclosing the current statement
+     */
+    switches: usize,
+    errors: &'a mut Vec<ResolveError>,
+}
+
+impl FunctionResolver<'_> {
+    fn resolve(&mut self, function: &Function) {
+        for argument in &function.arguments {
+            if self.declare(*argument, Binding::Parameter(self.scope.parameters.len())) {
+                self.scope.parameters.push(*argument);
+            }
+        }
+
+        // all declarations are collected first, so a label can be used before the statement it names
+        self.declare_statement(&function.body);
+        self.resolve_statement(&function.body);
+    }
+
+    /**
+     * Declare a name into the function scope
+     * Returns false if the name is already declared
+     */
+    fn declare(&mut self, name: Spanned<Symbol>, binding: Binding) -> bool {
+        let Some(previous) = self.scope.declare(name.value, binding) else {
+            return true;
+        };
+
+        let mut error = ResolveError::new(format!("Duplicate declaration of `{}`", name.value), name.span);
+
+        if let Some(span) = self.scope.declaration_span(previous) {
+            error = error.with_related(String::from("previously declared"), span);
+        }
+
+        self.errors.push(error);
+
+        false
+    }
+
+    fn declare_statement(&mut self, statement: &SpannedStatement) {
+        match &statement.value {
+            Statement::Auto(variables) => {
+                for variable in variables {
+                    if self.declare(variable.name, Binding::Auto(self.scope.autos.len())) {
+                        self.scope.autos.push(variable.clone());
+                    }
+                }
+            },
+            Statement::Extern(names) => {
+                for name in names {
+                    if !self.defined.contains(&name.value) {
+                        self.errors.push(ResolveError::new(format!("Undefined external name `{}`", name.value), name.span));
+                    }
+
+                    if self.declare(*name, Binding::Global(name.value)) {
+                        self.scope.externs.push(*name);
+                    }
+                }
+            },
+            Statement::Label { name, statement } => {
+                if self.declare(*name, Binding::Label(self.scope.labels.len())) {
+                    self.scope.labels.push(*name);
+                }
+
+                self.declare_statement(statement);
+            },
+            Statement::Block(statements) => statements.iter().for_each(|statement| self.declare_statement(statement)),
+            Statement::If { then, otherwise, .. } => {
+                self.declare_statement(then);
+
+                if let Some(otherwise) = otherwise {
+                    self.declare_statement(otherwise);
+                }
+            },
+            Statement::While { body, .. } | Statement::Switch { body, .. } => self.declare_statement(body),
+            Statement::Case { statement, .. } => self.declare_statement(statement),
+            Statement::Goto(_) | Statement::Return(_) | Statement::Expression(_) | Statement::Null => {},
+        }
+    }
+
+    fn resolve_statement(&mut self, statement: &SpannedStatement) {
+        match &statement.value {
+            Statement::Block(statements) => statements.iter().for_each(|statement| self.resolve_statement(statement)),
+            Statement::If { condition, then, otherwise } => {
+                self.resolve_expression(condition);
+                self.resolve_statement(then);
+
+                if let Some(otherwise) = otherwise {
+                    self.resolve_statement(otherwise);
+                }
+            },
+            Statement::While { condition, body } => {
+                self.resolve_expression(condition);
+                self.resolve_statement(body);
+            },
+            Statement::Switch { value, body } => {
+                self.resolve_expression(value);
+                self.switches += 1;
+                self.resolve_statement(body);
+                self.switches -= 1;
+            },
+            Statement::Case { value, statement: inner } => {
+                if self.switches == 0 {
+                    self.errors.push(ResolveError::new(String::from("Case outside of a switch"), statement.span));
+                }
+
+                self.resolve_expression(value);
+                self.resolve_statement(inner);
+            },
+            Statement::Label { statement, .. } => self.resolve_statement(statement),
+            Statement::Goto(expression) | Statement::Return(Some(expression)) | Statement::Expression(expression) => {
+                self.resolve_expression(expression);
+            },
+            Statement::Auto(_) | Statement::Extern(_) | Statement::Return(None) | Statement::Null => {},
+        }
+    }
+
+    fn resolve_expression(&mut self, expression: &SpannedExpression) {
+        match &expression.value {
+            Expression::Name { name, id } => match self.scope.lookup(*name) {
+                Some(binding) => self.scope.bind(*id, binding),
+                None => self.errors.push(ResolveError::new(format!("Undeclared name `{}`", name), expression.span)),
+            },
+            Expression::Call { function, arguments } => {
+                match &function.value {
+                    // a called function doesn't need to be declared by extrn
+                    Expression::Name { name, id } if self.scope.lookup(*name).is_none() => {
+                        if self.defined.contains(name) {
+                            self.scope.bind(*id, Binding::Global(*name));
+                        } else {
+                            self.errors.push(ResolveError::new(format!("Undefined function `{}`", name), function.span));
+                        }
+                    },
+                    _ => self.resolve_expression(function),
+                }
+
+                arguments.iter().for_each(|argument| self.resolve_expression(argument));
+            },
+            Expression::Assign { target, value, .. } => {
+                self.resolve_lvalue(target);
+                self.resolve_expression(value);
+            },
+            Expression::Unary { operator, operand } if operator.is_lvalue_operator() => self.resolve_lvalue(operand),
+            Expression::Binary { left, right, .. }
+            | Expression::Index { vector: left, index: right } => {
+                self.resolve_expression(left);
+                self.resolve_expression(right);
+            },
+            Expression::Unary { operand, .. } => self.resolve_expression(operand),
+            Expression::Conditional { condition, then, otherwise } => {
+                self.resolve_expression(condition);
+                self.resolve_expression(then);
+                self.resolve_expression(otherwise);
+            },
+            Expression::Char(chars) if !self.word_size.fits_chars(chars) => {
+                self.errors.push(char_error(chars, self.word_size, expression.span));
+            },
+            Expression::Integer(_) | Expression::Char(_) | Expression::String(_) => {},
+        }
+    }
+
+    /**
+     * Resolve an expression which must designate a word : a variable, an indirection or a vector element
+     */
+    fn resolve_lvalue(&mut self, expression: &SpannedExpression) {
+        self.resolve_expression(expression);
+
+        let is_lvalue = match &expression.value {
+            Expression::Name { name, .. } => !matches!(self.scope.lookup(*name), Some(Binding::Label(_))),
+            Expression::Unary { operator: UnaryOperator::Indirection, .. } | Expression::Index { .. } => true,
+            _ => false,
+        };
+
+        if !is_lvalue {
+            self.errors.push(ResolveError::new(String::from("Expecting an lvalue"), expression.span));
+        }
+    }
+}
+
+fn char_error(chars: &[char; 4], word_size: WordSize, span: Span) -> ResolveError {
+    ResolveError::new(format!("The char constant {} does not fit into a {} word", Token::Char(*chars), word_size), span)
+}
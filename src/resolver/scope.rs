@@ -0,0 +1,116 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::parser::ast::{AutoVariable, NodeId};
+use crate::tokenizer::{Span, Spanned, Symbol};
+
+/**
+ * The declaration a name refers to
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Binding {
+    /**
+     * The function argument at the given index
+     */
+    Parameter(usize),
+
+    /**
+     * The auto variable at the given index of FunctionScope::autos
+     */
+    Auto(usize),
+
+    /**
+     * A global variable or function, declared by extrn
+     * A called function is implicitly global when it's not declared
+     */
+    Global(Symbol),
+
+    /**
+     * The label at the given index of FunctionScope::labels
+     */
+    Label(usize),
+}
+
+/**
+ * The names declared into a function, and the declaration of each name used by its body
+ * Declarations are visible in the whole function, whatever the block they appear in
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FunctionScope {
+    pub parameters: Vec<Spanned<Symbol>>,
+    pub autos: Vec<AutoVariable>,
+    /**
+     * The names declared by extrn
+     */
+    pub externs: Vec<Spanned<Symbol>>,
+    pub labels: Vec<Spanned<Symbol>>,
+    declarations: HashMap<Symbol, Binding>,
+    /**
+     * The bindings of the names used by the body, indexed by the id of the name expression
+     */
+    uses: HashMap<NodeId, Binding>,
+}
+
+impl FunctionScope {
+    /**
+     * Get the declaration of a name into the function
+     * Implicitly global functions are not declared
+     */
+    pub fn lookup(&self, name: Symbol) -> Option<Binding> {
+        self.declarations.get(&name).copied()
+    }
+
+    /**
+     * Get the declaration of the name expression with the given id
+     */
+    pub fn binding(&self, id: NodeId) -> Option<Binding> {
+        self.uses.get(&id).copied()
+    }
+
+    /**
+     * Get the location of a declaration
+     * Returns None for a global which is not declared by extrn
+     */
+    pub fn declaration_span(&self, binding: Binding) -> Option<Span> {
+        match binding {
+            Binding::Parameter(index) => self.parameters.get(index).map(|parameter| parameter.span),
+            Binding::Auto(index) => self.autos.get(index).map(|auto| auto.name.span),
+            Binding::Global(name) => self.externs.iter().find(|extern_name| extern_name.value == name).map(|name| name.span),
+            Binding::Label(index) => self.labels.get(index).map(|label| label.span),
+        }
+    }
+
+    /**
+     * Declare a name, unless it's already declared
+     * Returns the previous declaration of the name
+     */
+    pub(super) fn declare(&mut self, name: Symbol, binding: Binding) -> Option<Binding> {
+        match self.declarations.get(&name) {
+            Some(previous) => Some(*previous),
+            None => {
+                self.declarations.insert(name, binding);
+                None
+            },
+        }
+    }
+
+    pub(super) fn bind(&mut self, id: NodeId, binding: Binding) {
+        self.uses.insert(id, binding);
+    }
+}
+
+/**
+ * The result of the resolver : the scope of each function of the program, indexed by function name
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Resolution {
+    pub(super) scopes: BTreeMap<String, FunctionScope>,
+}
+
+impl Resolution {
+    /**
+     * Get the scope of the function with the given name
+     */
+    pub fn scope(&self, function: &str) -> Option<&FunctionScope> {
+        self.scopes.get(function)
+    }
+}
@@ -1,69 +1,579 @@
-pub use tokenizer::Tokenizer;
-pub use tokens::Token;
+pub use dialect::Dialect;
+pub use operator::Operator;
+pub use span::{Position, Span, Spanned};
+pub use stream::{ReaderTokens, Tokens};
+pub use symbol::Symbol;
+pub use tokenizer::{TokenError, Tokenizer};
+pub use tokens::{quote, Token};
+pub use trivia::{LosslessToken, LosslessTokens, Trivia};
+pub use util::{read_source, ReadError};
 
+mod dialect;
+mod operator;
+mod span;
+mod stream;
+mod symbol;
 mod tokens;
+#[allow(clippy::module_inception)]
 mod tokenizer;
+mod trivia;
 mod util;
 
 #[cfg(test)]
 mod test {
+    use std::io::Read;
     use std::path::Path;
 
-    use crate::tokenizer::Token;
+    use crate::tokenizer::{read_source, Dialect, LosslessTokens, Operator, Position, ReadError, Span, Spanned, Symbol, Token, Trivia};
     use crate::tokenizer::Tokenizer;
 
+    fn tokenize(source: &str) -> Tokenizer {
+        let mut tokenizer = Tokenizer::new();
+
+        source.chars().for_each(|c| tokenizer.push(c).unwrap());
+        tokenizer.finalize().unwrap();
+
+        tokenizer
+    }
+
+    #[test]
+    fn read_file_success() {
+        let tokenizer = Tokenizer::new().read_file(Path::new("example/printn.b")).unwrap();
+        let tokens = tokenizer.into_tokens();
+
+        assert_eq!(55, tokens.len());
+
+        assert_eq!(Token::Symbol(Symbol::intern("printn")), tokens[0].value);
+        assert_eq!(Token::OpeningParenthesis, tokens[1].value);
+        assert_eq!(Token::Symbol(Symbol::intern("n")), tokens[2].value);
+        assert_eq!(Token::Operator(Operator::Comma), tokens[3].value);
+        assert_eq!(Token::Symbol(Symbol::intern("b")), tokens[4].value);
+        assert_eq!(Token::ClosingParenthesis, tokens[5].value);
+        assert_eq!(Token::OpeningBrace, tokens[6].value);
+        assert_eq!(Token::Extern, tokens[7].value);
+        assert_eq!(Token::Symbol(Symbol::intern("putchar")), tokens[8].value);
+        assert_eq!(Token::EndOfStatement, tokens[9].value);
+        assert_eq!(Token::Auto, tokens[10].value);
+        assert_eq!(Token::Symbol(Symbol::intern("a")), tokens[11].value);
+        assert_eq!(Token::EndOfStatement, tokens[12].value);
+        assert_eq!(Token::If, tokens[13].value);
+        assert_eq!(Token::OpeningParenthesis, tokens[14].value);
+        assert_eq!(Token::Symbol(Symbol::intern("a")), tokens[15].value);
+        assert_eq!(Token::Operator(Operator::Assign), tokens[16].value);
+        assert_eq!(Token::Symbol(Symbol::intern("n")), tokens[17].value);
+        assert_eq!(Token::Operator(Operator::Slash), tokens[18].value);
+        assert_eq!(Token::Symbol(Symbol::intern("b")), tokens[19].value);
+        assert_eq!(Token::ClosingParenthesis, tokens[20].value);
+        assert_eq!(Token::Symbol(Symbol::intern("printn")), tokens[21].value);
+        assert_eq!(Token::OpeningParenthesis, tokens[22].value);
+        assert_eq!(Token::Symbol(Symbol::intern("a")), tokens[23].value);
+        assert_eq!(Token::Operator(Operator::Comma), tokens[24].value);
+        assert_eq!(Token::Symbol(Symbol::intern("b")), tokens[25].value);
+        assert_eq!(Token::ClosingParenthesis, tokens[26].value);
+        assert_eq!(Token::EndOfStatement, tokens[27].value);
+        assert_eq!(Token::Symbol(Symbol::intern("putchar")), tokens[28].value);
+        assert_eq!(Token::OpeningParenthesis, tokens[29].value);
+        assert_eq!(Token::Symbol(Symbol::intern("n")), tokens[30].value);
+        assert_eq!(Token::Operator(Operator::Percent), tokens[31].value);
+        assert_eq!(Token::Symbol(Symbol::intern("b")), tokens[32].value);
+        assert_eq!(Token::Operator(Operator::Plus), tokens[33].value);
+        assert_eq!(Token::Char(['0', '\0', '\0', '\0']), tokens[34].value);
+        assert_eq!(Token::ClosingParenthesis, tokens[35].value);
+        assert_eq!(Token::EndOfStatement, tokens[36].value);
+        assert_eq!(Token::ClosingBrace, tokens[37].value);
+    }
+
     #[test]
-    fn from_file_success() {
-        let tokenizer = Tokenizer::from_file(Path::new("example/printn.b")).unwrap();
-        let tokens = tokenizer.tokens();
-
-        assert_eq!(38, tokens.len());
-
-        assert_eq!(Token::Symbol(String::from("printn")), tokens[0]);
-        assert_eq!(Token::OpeningParenthesis, tokens[1]);
-        assert_eq!(Token::Symbol(String::from("n")), tokens[2]);
-        assert_eq!(Token::Operator(String::from(",")), tokens[3]);
-        assert_eq!(Token::Symbol(String::from("b")), tokens[4]);
-        assert_eq!(Token::ClosingParenthesis, tokens[5]);
-        assert_eq!(Token::OpeningBrace, tokens[6]);
-        assert_eq!(Token::Extern, tokens[7]);
-        assert_eq!(Token::Symbol(String::from("putchar")), tokens[8]);
-        assert_eq!(Token::EndOfStatement, tokens[9]);
-        assert_eq!(Token::Auto, tokens[10]);
-        assert_eq!(Token::Symbol(String::from("a")), tokens[11]);
-        assert_eq!(Token::EndOfStatement, tokens[12]);
-        assert_eq!(Token::If, tokens[13]);
-        assert_eq!(Token::OpeningParenthesis, tokens[14]);
-        assert_eq!(Token::Symbol(String::from("a")), tokens[15]);
-        assert_eq!(Token::Operator(String::from("=")), tokens[16]);
-        assert_eq!(Token::Symbol(String::from("n")), tokens[17]);
-        assert_eq!(Token::Operator(String::from("/")), tokens[18]);
-        assert_eq!(Token::Symbol(String::from("b")), tokens[19]);
-        assert_eq!(Token::ClosingParenthesis, tokens[20]);
-        assert_eq!(Token::Symbol(String::from("printn")), tokens[21]);
-        assert_eq!(Token::OpeningParenthesis, tokens[22]);
-        assert_eq!(Token::Symbol(String::from("a")), tokens[23]);
-        assert_eq!(Token::Operator(String::from(",")), tokens[24]);
-        assert_eq!(Token::Symbol(String::from("b")), tokens[25]);
-        assert_eq!(Token::ClosingParenthesis, tokens[26]);
-        assert_eq!(Token::EndOfStatement, tokens[27]);
-        assert_eq!(Token::Symbol(String::from("putchar")), tokens[28]);
-        assert_eq!(Token::OpeningParenthesis, tokens[29]);
-        assert_eq!(Token::Symbol(String::from("n")), tokens[30]);
-        assert_eq!(Token::Operator(String::from("%")), tokens[31]);
-        assert_eq!(Token::Symbol(String::from("b")), tokens[32]);
-        assert_eq!(Token::Operator(String::from("+")), tokens[33]);
-        assert_eq!(Token::Char(['0', '\0', '\0', '\0']), tokens[34]);
-        assert_eq!(Token::ClosingParenthesis, tokens[35]);
-        assert_eq!(Token::EndOfStatement, tokens[36]);
-        assert_eq!(Token::ClosingBrace, tokens[37]);
-    }
-
-    #[test]
-    fn from_file_not_found() {
-        match Tokenizer::from_file(Path::new("not_found")) {
-            Ok(_) => assert!(false, "An error should be returned"),
-            Err(e) => assert_eq!("No such file or directory (os error 2)", e.to_string()),
+    fn tokens_spans() {
+        let tokens = tokenize("foo(a, 12)\n  'ab' \"str\";").into_tokens();
+
+        assert_eq!(9, tokens.len());
+        assert_eq!(Span::new(Position::new(0, 1, 1), Position::new(3, 1, 4)), tokens[0].span);
+        assert_eq!(Span::new(Position::new(3, 1, 4), Position::new(4, 1, 5)), tokens[1].span);
+        assert_eq!(Span::new(Position::new(4, 1, 5), Position::new(5, 1, 6)), tokens[2].span);
+        assert_eq!(Span::new(Position::new(5, 1, 6), Position::new(6, 1, 7)), tokens[3].span);
+        assert_eq!(Span::new(Position::new(7, 1, 8), Position::new(9, 1, 10)), tokens[4].span);
+        assert_eq!(Span::new(Position::new(9, 1, 10), Position::new(10, 1, 11)), tokens[5].span);
+        assert_eq!(Span::new(Position::new(13, 2, 3), Position::new(17, 2, 7)), tokens[6].span);
+        assert_eq!(Span::new(Position::new(18, 2, 8), Position::new(23, 2, 13)), tokens[7].span);
+        assert_eq!(Span::new(Position::new(23, 2, 13), Position::new(24, 2, 14)), tokens[8].span);
+    }
+
+    #[test]
+    fn operators_spans() {
+        let tokens = tokenize("a<=b++ -1").into_tokens();
+
+        assert_eq!(Token::Operator(Operator::LessEqual), tokens[1].value);
+        assert_eq!(Span::new(Position::new(1, 1, 2), Position::new(3, 1, 4)), tokens[1].span);
+        assert_eq!(Token::Operator(Operator::Increment), tokens[3].value);
+        assert_eq!(Span::new(Position::new(4, 1, 5), Position::new(6, 1, 7)), tokens[3].span);
+        assert_eq!(Token::Operator(Operator::Minus), tokens[4].value);
+        assert_eq!(Span::new(Position::new(7, 1, 8), Position::new(8, 1, 9)), tokens[4].span);
+    }
+
+    #[test]
+    fn error_position() {
+        let mut tokenizer = Tokenizer::new();
+        let error = "a;\nb = 'abcde';".chars()
+            .map(|c| tokenizer.push(c))
+            .find(|r| r.is_err())
+            .unwrap()
+            .unwrap_err();
+
+        assert_eq!(Span::new(Position::new(7, 2, 5), Position::new(14, 2, 12)), error.span());
+        assert_eq!("'abcde'", error.text());
+        assert_eq!("TokenError: Invalid character size : must be between 1 and 4 at 2:5 near `'abcde'`", error.to_string());
+    }
+
+    #[test]
+    fn escape_sequences() {
+        let tokens = tokenize("\"a*nb*t*0*e*(*)***'*\"\" '*n' '*'' '***0'").into_tokens();
+
+        assert_eq!(4, tokens.len());
+        assert_eq!(Token::String(String::from("a\nb\t\0\u{4}{}*'\"")), tokens[0].value);
+        assert_eq!(Token::Char(['\n', '\0', '\0', '\0']), tokens[1].value);
+        assert_eq!(Token::Char(['\'', '\0', '\0', '\0']), tokens[2].value);
+        assert_eq!(Token::Char(['*', '\0', '\0', '\0']), tokens[3].value);
+        assert_eq!(Span::new(Position::new(33, 1, 34), Position::new(39, 1, 40)), tokens[3].span);
+    }
+
+    #[test]
+    fn unknown_escape_sequence() {
+        let mut tokenizer = Tokenizer::new();
+        let error = "x = \"a*qb\";".chars()
+            .map(|c| tokenizer.push(c))
+            .find(|r| r.is_err())
+            .unwrap()
+            .unwrap_err();
+
+        assert_eq!("Unknown escape sequence", error.message());
+        assert_eq!(Span::new(Position::new(6, 1, 7), Position::new(8, 1, 9)), error.span());
+        assert_eq!("*q", error.text());
+    }
+
+    #[test]
+    fn integer_literals() {
+        let tokens = tokenize("0 10 017 0777 2147483647 037777777777").into_tokens();
+
+        assert_eq!(Token::Integer(0), tokens[0].value);
+        assert_eq!(Token::Integer(10), tokens[1].value);
+        assert_eq!(Token::Integer(15), tokens[2].value);
+        assert_eq!(Token::Integer(511), tokens[3].value);
+        assert_eq!(Token::Integer(i32::MAX), tokens[4].value);
+        assert_eq!(Token::Integer(-1), tokens[5].value);
+    }
+
+    #[test]
+    fn integer_literals_errors() {
+        let error = |source: &str| {
+            let mut tokenizer = Tokenizer::new();
+
+            source.chars()
+                .map(|c| tokenizer.push(c))
+                .find(|r| r.is_err())
+                .unwrap_or_else(|| tokenizer.finalize())
+                .unwrap_err()
+        };
+
+        let e = error("a = 0128;");
+        assert_eq!("Invalid digit in octal literal", e.message());
+        assert_eq!("0128", e.text());
+        assert_eq!(Span::new(Position::new(4, 1, 5), Position::new(8, 1, 9)), e.span());
+
+        let e = error("a = 12ab;");
+        assert_eq!("Invalid digit in integer literal", e.message());
+        assert_eq!("12a", e.text());
+
+        let e = error("x\n  2147483648;");
+        assert_eq!("Integer literal overflow", e.message());
+        assert_eq!("2147483648", e.text());
+        assert_eq!(Span::new(Position::new(4, 2, 3), Position::new(14, 2, 13)), e.span());
+
+        let e = error("040000000000");
+        assert_eq!("Integer literal overflow", e.message());
+    }
+
+    fn operators(tokenizer: Tokenizer, source: &str) -> Vec<String> {
+        tokenizer.stream(source.chars())
+            .map(|t| match t.unwrap().value {
+                Token::Operator(op) => op.to_string(),
+                Token::Symbol(name) => name.to_string(),
+                Token::Integer(i) => i.to_string(),
+                t => panic!("unexpected token {:?}", t),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn compound_assignments() {
+        let tokens = operators(
+            Tokenizer::new(),
+            "a=+b a=-b a=*b a=/b a=%b a=<<b a=>>b a=&b a=|b a===b a=!=b a=<b a=<=b a=>b a=>=b"
+        );
+
+        assert_eq!(
+            vec![
+                "a", "=+", "b", "a", "=-", "b", "a", "=*", "b", "a", "=/", "b", "a", "=%", "b",
+                "a", "=<<", "b", "a", "=>>", "b", "a", "=&", "b", "a", "=|", "b", "a", "===", "b",
+                "a", "=!=", "b", "a", "=<", "b", "a", "=<=", "b", "a", "=>", "b", "a", "=>=", "b",
+            ],
+            tokens
+        );
+    }
+
+    #[test]
+    fn assignments_and_equality() {
+        let tokens = operators(Tokenizer::new(), "a = b == c a=!b a=b a==b a =");
+
+        assert_eq!(
+            vec!["a", "=", "b", "==", "c", "a", "=", "!", "b", "a", "=", "b", "a", "==", "b", "a", "="],
+            tokens
+        );
+    }
+
+    #[test]
+    fn historical_dialect() {
+        let tokens = operators(Tokenizer::with_dialect(Dialect::Historical), "a=-1 a =- 1 a=*b a=&b a=--b");
+
+        assert_eq!(
+            vec!["a", "=-", "1", "a", "=-", "1", "a", "=*", "b", "a", "=&", "b", "a", "=-", "-", "b"],
+            tokens
+        );
+    }
+
+    #[test]
+    fn modern_dialect() {
+        let source = "a=-1 a =- 1 a=*b a=&b a=--b a=!=b";
+        let tokens = operators(Tokenizer::with_dialect(Dialect::Modern), source);
+
+        assert_eq!(
+            vec!["a", "=", "-", "1", "a", "=-", "1", "a", "=", "*", "b", "a", "=", "&", "b", "a", "=", "--", "b", "a", "=!=", "b"],
+            tokens
+        );
+
+        let tokens: Vec<_> = Tokenizer::with_dialect(Dialect::Modern).stream(source.chars()).map(Result::unwrap).collect();
+        assert_eq!(Span::new(Position::new(1, 1, 2), Position::new(2, 1, 3)), tokens[1].span);
+        assert_eq!(Span::new(Position::new(2, 1, 3), Position::new(3, 1, 4)), tokens[2].span);
+        assert_eq!(Span::new(Position::new(24, 1, 25), Position::new(26, 1, 27)), tokens[17].span);
+    }
+
+    #[test]
+    fn display_literals_round_trip() {
+        let source = r#""x*n" "a*tb*e" "**/*"" '*'' 'a*0' '*0' "*(*)""#;
+        let tokens = tokenize(source).into_tokens();
+
+        assert_eq!(
+            vec![r#""x*n""#, r#""a*tb*e""#, r#""**/*"""#, "'*''", "'a'", "'*0'", r#""{}""#],
+            tokens.iter().map(|token| token.value.to_string()).collect::<Vec<_>>()
+        );
+
+        for token in tokens {
+            assert_eq!(vec![token.value.clone()], tokenize(&token.value.to_string()).into_tokens().into_iter().map(|t| t.value).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn modern_dialect_comments() {
+        let source = "x=/* c */5 x=/5 x =/ 5";
+
+        assert_eq!(
+            vec!["x", "=", "5", "x", "=/", "5", "x", "=/", "5"],
+            operators(Tokenizer::with_dialect(Dialect::Modern), source)
+        );
+        assert_eq!(
+            vec!["x", "=/", "*", "c", "*", "/", "5"],
+            operators(Tokenizer::with_dialect(Dialect::Historical), "x=/* c */5")
+        );
+
+        let tokens: Vec<_> = Tokenizer::with_dialect(Dialect::Modern).stream(source.chars()).map(Result::unwrap).collect();
+        assert_eq!(Span::new(Position::new(1, 1, 2), Position::new(2, 1, 3)), tokens[1].span);
+        assert_eq!(Span::new(Position::new(9, 1, 10), Position::new(10, 1, 11)), tokens[2].span);
+    }
+
+    #[test]
+    fn read_file_not_found() {
+        match Tokenizer::new().read_file(Path::new("not_found")) {
+            Ok(_) => panic!("An error should be returned"),
+            Err(ReadError::Token(e)) => panic!("An I/O error should be returned, got {}", e),
+            Err(ReadError::Io(e)) => assert_eq!("No such file or directory (os error 2)", e.to_string()),
+        }
+    }
+
+    /**
+     * Reader returning at most the given number of bytes on each call
+     */
+    struct SlowReader<'a> {
+        source: &'a [u8],
+        chunk: usize,
+    }
+
+    impl Read for SlowReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let size = self.chunk.min(buf.len()).min(self.source.len());
+
+            buf[..size].copy_from_slice(&self.source[..size]);
+            self.source = &self.source[size..];
+
+            Ok(size)
+        }
+    }
+
+    #[test]
+    fn from_string() {
+        let tokens = Tokenizer::from_string("a = 'é'; /* ça */ \"日本\";").unwrap().into_tokens();
+
+        assert_eq!(Token::Char(['é', '\0', '\0', '\0']), tokens[2].value);
+        assert_eq!(Token::String(String::from("日本")), tokens[4].value);
+        assert_eq!(Position::new(20, 1, 19), tokens[4].span.start);
+        assert_eq!(Position::new(28, 1, 23), tokens[4].span.end);
+
+        assert_eq!("TokenError: Integer literal overflow at 1:1 near `99999999999`", Tokenizer::from_string("99999999999").err().unwrap().to_string());
+    }
+
+    #[test]
+    fn read_split_characters() {
+        let source = "main() { putchar('é'); /* commentaire accentué */ putchar(\"→*n\"); }";
+
+        for chunk in 1..5 {
+            let tokenizer = Tokenizer::new().read_to_end(SlowReader { source: source.as_bytes(), chunk }).unwrap();
+
+            assert_eq!(Tokenizer::from_string(source).unwrap().into_tokens(), tokenizer.into_tokens());
         }
     }
+
+    #[test]
+    fn read_short_reads() {
+        let source = std::fs::read("example/printn.b").unwrap();
+        let tokenizer = Tokenizer::new().read_to_end(SlowReader { source: &source, chunk: 100 }).unwrap();
+
+        assert_eq!(Tokenizer::new().read_file(Path::new("example/printn.b")).unwrap().into_tokens(), tokenizer.into_tokens());
+    }
+
+    #[test]
+    fn read_with_dialect() {
+        let tokens = Tokenizer::with_dialect(Dialect::Modern).read_to_end("a=-1".as_bytes()).unwrap().into_tokens();
+
+        assert_eq!(Token::Operator(Operator::Assign), tokens[1].value);
+        assert_eq!(Token::Operator(Operator::Minus), tokens[2].value);
+    }
+
+    #[test]
+    fn read_invalid_encoding() {
+        let source: &[u8] = b"a;\nb = \"x\xFFy\";";
+
+        for chunk in [1, 3, 4096] {
+            let Err(ReadError::Token(error)) = Tokenizer::new().read_to_end(SlowReader { source, chunk }) else {
+                panic!("A token error should be returned");
+            };
+
+            assert_eq!("Invalid UTF-8 sequence", error.message());
+            assert_eq!(Span::new(Position::new(9, 2, 7), Position::new(10, 2, 8)), error.span());
+            assert_eq!("\\xFF", error.text());
+        }
+
+        let error = Tokenizer::new().read_to_end(&b"a = 'b\xC3"[..]).err().unwrap();
+        assert_eq!("TokenError: Invalid UTF-8 sequence at 1:7 near `\\xC3`", error.to_string());
+
+        let error = read_source(SlowReader { source, chunk: 2 }).err().unwrap();
+        assert_eq!("TokenError: Invalid UTF-8 sequence at 2:7 near `\\xFF`", error.to_string());
+        assert_eq!("a;\nb = \"é\";", read_source(SlowReader { source: "a;\nb = \"é\";".as_bytes(), chunk: 1 }).unwrap());
+    }
+
+    #[test]
+    fn stream_is_lazy() {
+        let mut tokens = Tokenizer::new().stream("a = 1; ".chars().cycle());
+
+        assert_eq!(Token::Symbol(Symbol::intern("a")), tokens.next().unwrap().unwrap().value);
+        assert_eq!(Token::Operator(Operator::Assign), tokens.next().unwrap().unwrap().value);
+        assert_eq!(Token::Integer(1), tokens.next().unwrap().unwrap().value);
+        assert_eq!(Span::new(Position::new(14, 1, 15), Position::new(15, 1, 16)), tokens.nth(5).unwrap().unwrap().span);
+    }
+
+    #[test]
+    fn read_stream() {
+        let tokens: Vec<_> = Tokenizer::new().read(std::io::repeat(b';')).take(3).map(Result::unwrap).collect();
+
+        assert_eq!(vec![Token::EndOfStatement; 3], tokens.iter().map(|token| token.value.clone()).collect::<Vec<_>>());
+
+        let source = std::fs::read("example/printn.b").unwrap();
+        let tokens: Vec<_> = Tokenizer::new().read(SlowReader { source: &source, chunk: 7 }).map(Result::unwrap).collect();
+
+        assert_eq!(Tokenizer::new().read_file(Path::new("example/printn.b")).unwrap().into_tokens(), tokens);
+
+        let mut tokens = Tokenizer::new().read(&b"a b \xFF"[..]);
+
+        assert_eq!(Token::Symbol(Symbol::intern("a")), tokens.next().unwrap().unwrap().value);
+        assert_eq!(Token::Symbol(Symbol::intern("b")), tokens.next().unwrap().unwrap().value);
+        assert_eq!("TokenError: Invalid UTF-8 sequence at 1:5 near `\\xFF`", tokens.next().unwrap().unwrap_err().to_string());
+        assert!(tokens.next().is_none());
+    }
+
+    #[test]
+    fn stream_stops_on_error() {
+        let tokens: Vec<_> = Tokenizer::new().stream("a; 'toolong' b;".chars()).collect();
+
+        assert_eq!(3, tokens.len());
+        assert_eq!(Token::Symbol(Symbol::intern("a")), tokens[0].as_ref().unwrap().value);
+        assert_eq!(Token::EndOfStatement, tokens[1].as_ref().unwrap().value);
+        assert_eq!("Invalid character size : must be between 1 and 4", tokens[2].as_ref().unwrap_err().message());
+    }
+
+    #[test]
+    fn interned_symbols() {
+        let tokens = tokenize("foo bar foo \"foo\"").into_tokens();
+
+        assert_eq!(tokens[0].value, tokens[2].value);
+        assert_ne!(tokens[0].value, tokens[1].value);
+        assert_eq!(Token::String(String::from("foo")), tokens[3].value);
+        assert_eq!(Symbol::intern("foo"), Symbol::intern(String::from("foo").as_str()));
+        assert_eq!("foo", Symbol::intern("foo").as_str());
+        assert!(Symbol::intern("foo") == *"foo");
+    }
+
+    #[test]
+    fn operator_symbols() {
+        let source = ", : ? ! + - * / % & | < <= > >= == != << >> ++ -- = =| =& === =!= =< =<= => =>= =<< =>> =+ =- =% =* =/";
+
+        for (token, symbol) in tokenize(source).into_tokens().iter().zip(source.split(' ')) {
+            match &token.value {
+                Token::Operator(operator) => {
+                    assert_eq!(Some(*operator), Operator::from_symbol(symbol));
+                    assert_eq!(symbol, operator.symbol());
+                    assert_eq!(symbol.starts_with('=') && symbol != "==", operator.is_assignment());
+                },
+                token => panic!("expecting operator, got {:?}", token),
+            }
+        }
+
+        assert_eq!(Some(Operator::Plus), Operator::AssignAdd.compound());
+        assert_eq!(Some(Operator::Equal), Operator::AssignEqual.compound());
+        assert_eq!(None, Operator::Assign.compound());
+        assert_eq!(None, Operator::from_symbol("=!"));
+    }
+
+    #[test]
+    fn comments_with_star_and_slash() {
+        let values = |source: &str| tokenize(source).into_tokens().into_iter().map(|token| token.value).collect::<Vec<_>>();
+
+        assert_eq!(vec![Token::Symbol(Symbol::intern("x"))], values("/* a * b / c */ x"));
+        assert_eq!(vec![Token::Symbol(Symbol::intern("x"))], values("/* ** / */ x"));
+        assert_eq!(vec![Token::Symbol(Symbol::intern("x"))], values("/***/ x /**/"));
+        assert_eq!(Span::new(Position::new(16, 1, 17), Position::new(17, 1, 18)), tokenize("/* a * b / c */ x").into_tokens()[0].span);
+    }
+
+    #[test]
+    fn comments_after_operators() {
+        let values = |source: &str| tokenize(source).into_tokens().into_iter().map(|token| token.value).collect::<Vec<_>>();
+
+        assert_eq!(vec![Token::Integer(1), Token::Operator(Operator::Plus), Token::Integer(2)], values("1+/* c */2"));
+        assert_eq!(vec![Token::Integer(1), Token::Operator(Operator::Minus), Token::Integer(2)], values("1-/* c */2"));
+        assert_eq!(vec![Token::Integer(1), Token::Operator(Operator::Plus), Token::Integer(2)], values("1+/**/2"));
+    }
+
+    #[test]
+    fn unterminated_constructs() {
+        let error = |source: &str| Tokenizer::from_string(source).err().unwrap();
+
+        let e = error("a;\n/* comment\nb;");
+        assert_eq!("Unterminated comment", e.message());
+        assert_eq!("/*", e.text());
+        assert_eq!(Span::new(Position::new(3, 2, 1), Position::new(16, 3, 3)), e.span());
+
+        let e = error("/* a * b / c");
+        assert_eq!("TokenError: Unterminated comment at 1:1 near `/*`", e.to_string());
+
+        let e = error("a = \"hello\nworld;");
+        assert_eq!("TokenError: Unterminated string at 1:5 near `\"hello`", e.to_string());
+        assert_eq!(Span::new(Position::new(4, 1, 5), Position::new(17, 2, 7)), e.span());
+
+        let e = error("a = \"ends with escape*");
+        assert_eq!("Unterminated string", e.message());
+
+        let e = error("a = 'ab");
+        assert_eq!("TokenError: Unterminated character constant at 1:5 near `'ab`", e.to_string());
+
+        assert!(Tokenizer::from_string("a /* b */ = \"c*n\" + '*n'; /**/").is_ok());
+        assert!(Tokenizer::from_string("a =!").is_ok());
+    }
+
+    #[test]
+    fn newline_in_char_constant() {
+        let e = Tokenizer::from_string("a = 'a\nb';").err().unwrap();
+
+        assert_eq!("Newline in character constant", e.message());
+        assert_eq!("'a", e.text());
+        assert_eq!(Span::new(Position::new(4, 1, 5), Position::new(6, 1, 7)), e.span());
+
+        // newlines are allowed into strings
+        assert_eq!(Token::String(String::from("a\nb")), tokenize("\"a\nb\"").into_tokens()[0].value);
+    }
+
+    fn tokenize_lossless(source: &str) -> LosslessTokens {
+        let mut tokenizer = Tokenizer::new().with_trivia();
+
+        source.chars().for_each(|c| tokenizer.push(c).unwrap());
+        tokenizer.finalize().unwrap();
+
+        tokenizer.into_lossless_tokens()
+    }
+
+    #[test]
+    fn lossless_round_trip() {
+        let sources = [
+            std::fs::read_to_string("example/printn.b").unwrap(),
+            String::from("  /* a */ /**/x=!y;a=-1 ;\tv 017, 'a*n', \"*(é*)\" ;\n\n/* end */\n"),
+            String::from("a=!"),
+            String::new(),
+        ];
+
+        for source in sources {
+            assert_eq!(source, tokenize_lossless(&source).to_string());
+        }
+    }
+
+    #[test]
+    fn lossless_trivia() {
+        let tokens = tokenize_lossless("/* doc */\nf() {\n  x =+ 017; /* octal */\n}\n");
+
+        assert_eq!(
+            vec![
+                Spanned::new(Trivia::Comment(String::from("/* doc */")), Span::new(Position::new(0, 1, 1), Position::new(9, 1, 10))),
+                Spanned::new(Trivia::Whitespace(String::from("\n")), Span::new(Position::new(9, 1, 10), Position::new(10, 2, 1))),
+            ],
+            tokens.tokens[0].leading
+        );
+        assert_eq!("f", tokens.tokens[0].text);
+        assert!(tokens.tokens[1].leading.is_empty());
+
+        assert_eq!("=+", tokens.tokens[5].text);
+        assert_eq!(Token::Integer(15), tokens.tokens[6].token.value);
+        assert_eq!("017", tokens.tokens[6].text);
+
+        assert_eq!(Trivia::Comment(String::from("/* octal */")), tokens.tokens[8].leading[1].value);
+        assert_eq!(Token::ClosingBrace, tokens.tokens[8].token.value);
+
+        assert_eq!(vec![Spanned::new(Trivia::Whitespace(String::from("\n")), Span::new(Position::new(41, 4, 2), Position::new(42, 5, 1)))], tokens.trailing);
+    }
+
+    #[test]
+    fn lossless_comment_with_star_and_slash() {
+        let source = "a /* x * y / z ** / */ = b; /*/ c */\n";
+        let tokens = tokenize_lossless(source);
+
+        assert_eq!(source, tokens.to_string());
+        assert_eq!(vec!["a", "=", "b", ";"], tokens.tokens.iter().map(|token| token.text.as_str()).collect::<Vec<_>>());
+        assert_eq!(Trivia::Comment(String::from("/* x * y / z ** / */")), tokens.tokens[1].leading[1].value);
+        assert_eq!(Trivia::Comment(String::from("/*/ c */")), tokens.trailing[1].value);
+    }
+
+    #[test]
+    fn lossless_without_trivia() {
+        let mut tokenizer = tokenize("a /* b */ =+ 017;");
+        let token = tokenizer.next_lossless_token().unwrap();
+
+        assert!(token.leading.is_empty());
+        assert_eq!("a", token.text);
+        assert_eq!(Token::Operator(Operator::AssignAdd), tokenizer.next_token().unwrap().value);
+        assert_eq!("15", tokenizer.next_lossless_token().unwrap().text);
+    }
 }
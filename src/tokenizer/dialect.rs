@@ -0,0 +1,20 @@
+/**
+ * Select how ambiguous sequences of the source are tokenized
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dialect {
+    /**
+     * The historical B behavior: an "=" followed by an operator always forms a compound assignment
+     * So "a=-1" is parsed as "a =- 1" (i.e. a = a - 1)
+     */
+    #[default]
+    Historical,
+
+    /**
+     * "=-", "=*" and "=&" are considered as compound assignment only when followed by a whitespace,
+     * otherwise the operator is considered as unary operator applied to the assigned value
+     * So "a=-1" is parsed as "a = -1", but "a =- 1" is still a compound assignment
+     * Likewise "a=/* c */1" is an assignment followed by a comment, not the compound assignment "=/"
+     */
+    Modern,
+}
@@ -0,0 +1,187 @@
+use std::fmt::{Display, Formatter};
+
+/**
+ * All operators of the language
+ * The meaning of some operators depends on their position : "-" is either the negation or the subtraction,
+ * "*" the indirection or the multiplication, and "&" the address or the bitwise and
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operator {
+    Comma,
+    Colon,
+    Question,
+    Not,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Ampersand,
+    Pipe,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+    ShiftLeft,
+    ShiftRight,
+    Increment,
+    Decrement,
+
+    /**
+     * The simple assignment "="
+     */
+    Assign,
+
+    /**
+     * The compound assignments : "=" followed by a binary operator
+     * Note: "===" is the compound assignment of the equality
+     */
+    AssignOr,
+    AssignAnd,
+    AssignEqual,
+    AssignNotEqual,
+    AssignLess,
+    AssignLessEqual,
+    AssignGreater,
+    AssignGreaterEqual,
+    AssignShiftLeft,
+    AssignShiftRight,
+    AssignAdd,
+    AssignSubtract,
+    AssignModulo,
+    AssignMultiply,
+    AssignDivide,
+}
+
+impl Operator {
+    /**
+     * Get the operator from its source representation
+     */
+    pub fn from_symbol(symbol: &str) -> Option<Operator> {
+        Some(match symbol {
+            "," => Operator::Comma,
+            ":" => Operator::Colon,
+            "?" => Operator::Question,
+            "!" => Operator::Not,
+            "+" => Operator::Plus,
+            "-" => Operator::Minus,
+            "*" => Operator::Star,
+            "/" => Operator::Slash,
+            "%" => Operator::Percent,
+            "&" => Operator::Ampersand,
+            "|" => Operator::Pipe,
+            "<" => Operator::Less,
+            "<=" => Operator::LessEqual,
+            ">" => Operator::Greater,
+            ">=" => Operator::GreaterEqual,
+            "==" => Operator::Equal,
+            "!=" => Operator::NotEqual,
+            "<<" => Operator::ShiftLeft,
+            ">>" => Operator::ShiftRight,
+            "++" => Operator::Increment,
+            "--" => Operator::Decrement,
+            "=" => Operator::Assign,
+            "=|" => Operator::AssignOr,
+            "=&" => Operator::AssignAnd,
+            "===" => Operator::AssignEqual,
+            "=!=" => Operator::AssignNotEqual,
+            "=<" => Operator::AssignLess,
+            "=<=" => Operator::AssignLessEqual,
+            "=>" => Operator::AssignGreater,
+            "=>=" => Operator::AssignGreaterEqual,
+            "=<<" => Operator::AssignShiftLeft,
+            "=>>" => Operator::AssignShiftRight,
+            "=+" => Operator::AssignAdd,
+            "=-" => Operator::AssignSubtract,
+            "=%" => Operator::AssignModulo,
+            "=*" => Operator::AssignMultiply,
+            "=/" => Operator::AssignDivide,
+            _ => return None,
+        })
+    }
+
+    /**
+     * Get the source representation of the operator
+     */
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Operator::Comma => ",",
+            Operator::Colon => ":",
+            Operator::Question => "?",
+            Operator::Not => "!",
+            Operator::Plus => "+",
+            Operator::Minus => "-",
+            Operator::Star => "*",
+            Operator::Slash => "/",
+            Operator::Percent => "%",
+            Operator::Ampersand => "&",
+            Operator::Pipe => "|",
+            Operator::Less => "<",
+            Operator::LessEqual => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterEqual => ">=",
+            Operator::Equal => "==",
+            Operator::NotEqual => "!=",
+            Operator::ShiftLeft => "<<",
+            Operator::ShiftRight => ">>",
+            Operator::Increment => "++",
+            Operator::Decrement => "--",
+            Operator::Assign => "=",
+            Operator::AssignOr => "=|",
+            Operator::AssignAnd => "=&",
+            Operator::AssignEqual => "===",
+            Operator::AssignNotEqual => "=!=",
+            Operator::AssignLess => "=<",
+            Operator::AssignLessEqual => "=<=",
+            Operator::AssignGreater => "=>",
+            Operator::AssignGreaterEqual => "=>=",
+            Operator::AssignShiftLeft => "=<<",
+            Operator::AssignShiftRight => "=>>",
+            Operator::AssignAdd => "=+",
+            Operator::AssignSubtract => "=-",
+            Operator::AssignModulo => "=%",
+            Operator::AssignMultiply => "=*",
+            Operator::AssignDivide => "=/",
+        }
+    }
+
+    /**
+     * Check if the operator is a simple or compound assignment
+     */
+    pub fn is_assignment(&self) -> bool {
+        *self == Operator::Assign || self.compound().is_some()
+    }
+
+    /**
+     * Get the operator applied by a compound assignment, like "+" for "=+"
+     * Returns None if the operator is not a compound assignment
+     */
+    pub fn compound(&self) -> Option<Operator> {
+        match self {
+            Operator::AssignOr => Some(Operator::Pipe),
+            Operator::AssignAnd => Some(Operator::Ampersand),
+            Operator::AssignEqual => Some(Operator::Equal),
+            Operator::AssignNotEqual => Some(Operator::NotEqual),
+            Operator::AssignLess => Some(Operator::Less),
+            Operator::AssignLessEqual => Some(Operator::LessEqual),
+            Operator::AssignGreater => Some(Operator::Greater),
+            Operator::AssignGreaterEqual => Some(Operator::GreaterEqual),
+            Operator::AssignShiftLeft => Some(Operator::ShiftLeft),
+            Operator::AssignShiftRight => Some(Operator::ShiftRight),
+            Operator::AssignAdd => Some(Operator::Plus),
+            Operator::AssignSubtract => Some(Operator::Minus),
+            Operator::AssignModulo => Some(Operator::Percent),
+            Operator::AssignMultiply => Some(Operator::Star),
+            Operator::AssignDivide => Some(Operator::Slash),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
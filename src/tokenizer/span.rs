@@ -0,0 +1,120 @@
+use std::fmt::{Display, Formatter};
+
+/**
+ * A location into the source code
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Position {
+    /**
+     * Offset in bytes from the start of the source
+     */
+    pub offset: usize,
+
+    /**
+     * The line number, starting at 1
+     */
+    pub line: usize,
+
+    /**
+     * The column number (in characters), starting at 1
+     */
+    pub column: usize,
+}
+
+impl Position {
+    pub fn new(offset: usize, line: usize, column: usize) -> Position {
+        Position {
+            offset,
+            line,
+            column,
+        }
+    }
+
+    /**
+     * The position of the first character of a source
+     */
+    pub fn start() -> Position {
+        Position::new(0, 1, 1)
+    }
+
+    /**
+     * Compute the position following the given character
+     */
+    pub fn next(&self, c: char) -> Position {
+        if c == '\n' {
+            Position::new(self.offset + c.len_utf8(), self.line + 1, 1)
+        } else {
+            Position::new(self.offset + c.len_utf8(), self.line, self.column + 1)
+        }
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/**
+ * A range of the source code
+ * The start position is inclusive, and the end is exclusive
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Span {
+        Span {
+            start,
+            end,
+        }
+    }
+
+    /**
+     * Create a span which covers both spans
+     */
+    pub fn merge(&self, other: Span) -> Span {
+        let start = if other.start.offset < self.start.offset { other.start } else { self.start };
+        let end = if other.end.offset > self.end.offset { other.end } else { self.end };
+
+        Span::new(start, end)
+    }
+
+    /**
+     * Get the length of the span in bytes
+     */
+    pub fn len(&self) -> usize {
+        self.end.offset - self.start.offset
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.start)
+    }
+}
+
+/**
+ * Attach a source span to a value
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spanned<T> {
+    pub value: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(value: T, span: Span) -> Spanned<T> {
+        Spanned {
+            value,
+            span,
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use std::io::Read;
+
+use crate::tokenizer::util::Decoder;
+use crate::tokenizer::{ReadError, Spanned, Token, TokenError, Tokenizer};
+
+/**
+ * Lazy iterator over the tokens of a source
+ * Characters are pulled from the source only when a new token is requested,
+ * so the whole source is never tokenized ahead of its consumer
+ *
+ * The iteration stops after the first error
+ */
+pub struct Tokens<I> {
+    chars: I,
+    tokenizer: Tokenizer,
+    /**
+     * Error raised by the tokenizer, returned after the tokens completed before it
+     */
+    error: Option<TokenError>,
+    finished: bool,
+}
+
+impl<I: Iterator<Item = char>> Tokens<I> {
+    pub fn new(tokenizer: Tokenizer, chars: I) -> Tokens<I> {
+        Tokens {
+            chars,
+            tokenizer,
+            error: None,
+            finished: false,
+        }
+    }
+}
+
+impl<I: Iterator<Item = char>> Iterator for Tokens<I> {
+    type Item = Result<Spanned<Token>, TokenError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(token) = self.tokenizer.next_token() {
+                return Some(Ok(token));
+            }
+
+            if self.finished {
+                return self.error.take().map(Err);
+            }
+
+            let result = match self.chars.next() {
+                Some(c) => self.tokenizer.push(c),
+                None => {
+                    self.finished = true;
+                    self.tokenizer.finalize()
+                },
+            };
+
+            if let Err(error) = result {
+                self.finished = true;
+                self.error = Some(error);
+            }
+        }
+    }
+}
+
+/**
+ * Lazy iterator over the tokens of a UTF-8 reader
+ * The reader is read by chunks, only when the completed tokens are consumed,
+ * so a file or stdin can be parsed in one pass without buffering the whole source
+ *
+ * The iteration stops after the first error
+ */
+pub struct ReaderTokens<R> {
+    decoder: Decoder<R>,
+    tokenizer: Tokenizer,
+    /**
+     * Error raised by the reader or the tokenizer, returned after the tokens completed before it
+     */
+    error: Option<ReadError>,
+    finished: bool,
+}
+
+impl<R: Read> ReaderTokens<R> {
+    pub fn new(tokenizer: Tokenizer, reader: R) -> ReaderTokens<R> {
+        ReaderTokens {
+            decoder: Decoder::new(reader),
+            tokenizer,
+            error: None,
+            finished: false,
+        }
+    }
+
+    /**
+     * Push the next chunk of the reader into the tokenizer
+     */
+    fn push_chunk(&mut self) -> Result<(), ReadError> {
+        match self.decoder.read_chunk()? {
+            Some(chunk) => {
+                for c in chunk.chars() {
+                    self.tokenizer.push(c)?;
+                }
+            },
+            None => {
+                self.finished = true;
+                self.tokenizer.finalize()?;
+            },
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for ReaderTokens<R> {
+    type Item = Result<Spanned<Token>, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(token) = self.tokenizer.next_token() {
+                return Some(Ok(token));
+            }
+
+            if self.finished {
+                return self.error.take().map(Err);
+            }
+
+            if let Err(error) = self.push_chunk() {
+                self.finished = true;
+                self.error = Some(error);
+            }
+        }
+    }
+}
+
+impl Tokenizer {
+    /**
+     * Tokenize the characters lazily, using this tokenizer configuration
+     */
+    pub fn stream<I: IntoIterator<Item = char>>(self, chars: I) -> Tokens<I::IntoIter> {
+        Tokens::new(self, chars.into_iter())
+    }
+
+    /**
+     * Tokenize the UTF-8 source of the reader lazily, using this tokenizer configuration
+     */
+    pub fn read<R: Read>(self, reader: R) -> ReaderTokens<R> {
+        ReaderTokens::new(self, reader)
+    }
+}
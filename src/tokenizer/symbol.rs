@@ -0,0 +1,126 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{BuildHasherDefault, Hash, Hasher};
+use std::sync::{Mutex, OnceLock};
+
+/**
+ * An interned name
+ * Each distinct name is stored once, so a symbol is a small Copy value,
+ * and comparing two symbols does not compare their strings
+ *
+ * Interned names are never freed, which is fine as the set of names of the programs is bounded.
+ * The string literals are not interned, since their contents are not bounded.
+ */
+#[derive(Clone, Copy)]
+pub struct Symbol(&'static str);
+
+/**
+ * Fast non-cryptographic hash of the names (FxHash algorithm)
+ * Names are short, so the default SipHash is significantly slower here
+ */
+#[derive(Default)]
+struct NameHasher(u64);
+
+impl Hasher for NameHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+        for chunk in bytes.chunks(8) {
+            let mut word = [0u8; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+
+            self.0 = (self.0.rotate_left(5) ^ u64::from_le_bytes(word)).wrapping_mul(SEED);
+        }
+    }
+}
+
+type Interner = HashSet<&'static str, BuildHasherDefault<NameHasher>>;
+
+fn interner() -> &'static Mutex<Interner> {
+    static INTERNER: OnceLock<Mutex<Interner>> = OnceLock::new();
+
+    INTERNER.get_or_init(|| Mutex::new(Interner::default()))
+}
+
+impl Symbol {
+    /**
+     * Get the symbol of the string, storing it on the first call
+     */
+    pub fn intern(string: &str) -> Symbol {
+        let mut interner = interner().lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(interned) = interner.get(string) {
+            return Symbol(interned);
+        }
+
+        let interned: &'static str = Box::leak(string.to_owned().into_boxed_str());
+        interner.insert(interned);
+
+        Symbol(interned)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        self.0
+    }
+}
+
+/**
+ * The symbols already interned by a tokenizer
+ * Looking them up first avoids locking the global interner for each occurrence of a name
+ */
+#[derive(Debug, Clone, Default)]
+pub(super) struct SymbolCache(HashMap<&'static str, Symbol, BuildHasherDefault<NameHasher>>);
+
+impl SymbolCache {
+    pub(super) fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(symbol) = self.0.get(name) {
+            return *symbol;
+        }
+
+        let symbol = Symbol::intern(name);
+        self.0.insert(symbol.as_str(), symbol);
+
+        symbol
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Debug for Symbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+/**
+ * Each string is interned once, so symbols are compared by address
+ */
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Symbol) -> bool {
+        std::ptr::eq(self.0, other.0)
+    }
+}
+
+impl Eq for Symbol {
+
+}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::ptr::hash(self.0, state)
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
@@ -1,10 +1,14 @@
+use std::collections::VecDeque;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
-use crate::tokenizer::Token;
+use crate::tokenizer::symbol::SymbolCache;
+use crate::tokenizer::trivia::TriviaCollector;
+use crate::tokenizer::{Dialect, Operator, Position, Span, Spanned, Token};
 
 enum TokenizerState {
     Empty,
     AmbiguousOperator,
+    Assignment,
     SymbolOrKeyword,
     Comment,
     Char,
@@ -13,28 +17,83 @@ enum TokenizerState {
 }
 
 pub struct Tokenizer {
-    tokens: Vec<Token>,
+    /**
+     * Completed tokens, not yet taken by the caller
+     */
+    pub(super) tokens: VecDeque<Spanned<Token>>,
     buffer: String,
     state: TokenizerState,
+    dialect: Dialect,
+    /**
+     * Position of the character currently pushed
+     */
+    position: Position,
+    /**
+     * Start position of the token stored into the buffer
+     */
+    start: Position,
+    /**
+     * Position of the escape character '*' when parsing an escape sequence in a string or char
+     */
+    escape: Option<Position>,
+    /**
+     * Comments and whitespaces, kept only in trivia mode
+     */
+    pub(super) trivia: Option<TriviaCollector>,
+    symbols: SymbolCache,
 }
 
 #[derive(Debug, Clone)]
 pub struct TokenError {
-    message: &'static str
-    // @todo add token positions
+    message: &'static str,
+    span: Span,
+    text: String,
 }
 
 impl TokenError {
-    fn new(message: &'static str) -> TokenError {
+    fn new(message: &'static str, span: Span, text: String) -> TokenError {
         TokenError {
             message,
+            span,
+            text,
         }
     }
+
+    /**
+     * Create the error for bytes which are not valid UTF-8, located at the given position
+     */
+    pub(super) fn invalid_encoding(position: Position, bytes: &[u8]) -> TokenError {
+        let end = Position::new(position.offset + bytes.len(), position.line, position.column + 1);
+
+        TokenError::new(
+            "Invalid UTF-8 sequence",
+            Span::new(position, end),
+            bytes.iter().map(|byte| format!("\\x{:02X}", byte)).collect()
+        )
+    }
+
+    pub fn message(&self) -> &'static str {
+        self.message
+    }
+
+    /**
+     * The location of the invalid source
+     */
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /**
+     * The offending source text
+     */
+    pub fn text(&self) -> &str {
+        self.text.as_str()
+    }
 }
 
 impl Display for TokenError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "TokenError: {}", self.message)
+        write!(f, "TokenError: {} at {} near `{}`", self.message, self.span, self.text)
     }
 }
 
@@ -46,25 +105,116 @@ type Result<T> = std::result::Result<T, TokenError>;
 
 impl Tokenizer {
     pub fn new() -> Tokenizer {
+        Self::with_dialect(Dialect::default())
+    }
+
+    pub fn with_dialect(dialect: Dialect) -> Tokenizer {
         Tokenizer {
-            tokens: Vec::new(),
+            tokens: VecDeque::new(),
             buffer: String::new(),
             state: TokenizerState::Empty,
+            dialect,
+            position: Position::start(),
+            start: Position::start(),
+            escape: None,
+            trivia: None,
+            symbols: SymbolCache::default(),
         }
     }
 
-    pub fn tokens(&self) -> Vec<Token> {
-        self.tokens.clone()
+    /**
+     * Keep comments and whitespaces, so the source can be reproduced from the tokens
+     * See next_lossless_token()
+     */
+    pub fn with_trivia(mut self) -> Tokenizer {
+        self.trivia = Some(TriviaCollector::new());
+        self
+    }
+
+    /**
+     * Get all the completed tokens
+     */
+    pub fn into_tokens(self) -> Vec<Spanned<Token>> {
+        self.tokens.into()
+    }
+
+    /**
+     * Take the next completed token, if any
+     * Pushed characters may not immediately complete a token, so None does not mean the end of the source
+     */
+    pub fn next_token(&mut self) -> Option<Spanned<Token>> {
+        if let Some(trivia) = self.trivia.as_mut() {
+            trivia.skip();
+        }
+
+        self.tokens.pop_front()
     }
 
+    /**
+     * Save the last token at the end of the source
+     * Fails if a comment, string or char constant is not terminated
+     */
     pub fn finalize(&mut self) -> Result<()> {
-        return self.save_token();
+        let unterminated = match self.state {
+            TokenizerState::Comment => Some(("Unterminated comment", String::from("/*"))),
+            TokenizerState::String => Some(("Unterminated string", self.unterminated_text('"'))),
+            TokenizerState::Char => Some(("Unterminated character constant", self.unterminated_text('\''))),
+            _ => None,
+        };
+
+        if let Some((message, text)) = unterminated {
+            return Err(TokenError::new(message, Span::new(self.start, self.position), text));
+        }
+
+        if matches!(self.state, TokenizerState::Assignment) {
+            self.save_assignment(false)?;
+        }
+
+        self.save_token()?;
+
+        if let Some(trivia) = self.trivia.as_mut() {
+            trivia.finalize();
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Complete the token, after all previous tokens
+     */
+    fn emit(&mut self, token: Spanned<Token>) {
+        if let Some(trivia) = self.trivia.as_mut() {
+            trivia.complete(token.span);
+        }
+
+        self.tokens.push_back(token);
+    }
+
+    /**
+     * The beginning of an unterminated string or char constant, up to the end of its first line
+     */
+    fn unterminated_text(&self, delimiter: char) -> String {
+        let line = self.buffer.lines().next().unwrap_or_default();
+
+        format!("{}{}", delimiter, line)
     }
 
     pub fn push(&mut self, c: char) -> Result<()> {
+        if let Some(trivia) = self.trivia.as_mut() {
+            trivia.push(c);
+        }
+
+        let result = self.push_state(c);
+        self.position = self.position.next(c);
+
+        result
+    }
+
+    fn push_state(&mut self, c: char) -> Result<()> {
         match self.state {
             TokenizerState::Empty => self.push_empty_state(c),
             TokenizerState::AmbiguousOperator => self.push_ambiguous_state(c),
+            TokenizerState::Assignment => self.push_assignment_state(c),
             TokenizerState::SymbolOrKeyword => self.push_symbol_or_keyword_state(c),
             TokenizerState::Comment => self.push_comment_state(c),
             TokenizerState::Char => self.push_char_state(c),
@@ -76,36 +226,42 @@ impl Tokenizer {
     fn push_empty_state(&mut self, c: char) -> Result<()> {
         match c {
             c if c.is_whitespace() => Ok(()),
-            c if c.is_numeric() => {
-                self.state = TokenizerState::Numeric;
+            c if c.is_ascii_digit() => {
+                self.begin(TokenizerState::Numeric);
                 self.buffer.push(c);
 
                 Ok(())
             }
             '"' => {
-                self.state = TokenizerState::String;
+                self.begin(TokenizerState::String);
                 Ok(())
             }
             '\'' => {
-                self.state = TokenizerState::Char;
+                self.begin(TokenizerState::Char);
                 Ok(())
             }
-            '(' => Ok(self.tokens.push(Token::OpeningParenthesis)),
-            ')' => Ok(self.tokens.push(Token::ClosingParenthesis)),
-            '[' => Ok(self.tokens.push(Token::OpeningBracket)),
-            ']' => Ok(self.tokens.push(Token::ClosingBracket)),
-            '{' => Ok(self.tokens.push(Token::OpeningBrace)),
-            '}' => Ok(self.tokens.push(Token::ClosingBrace)),
-            ';' => Ok(self.tokens.push(Token::EndOfStatement)),
-            ','|':'|'|'|'&'|'%'|'*'|'?' => Ok(self.tokens.push(Token::Operator(String::from(c)))),
-            '+'|'-'|'/'|'!'|'='|'>'|'<' => {
-                self.state = TokenizerState::AmbiguousOperator;
+            '(' => self.push_single(Token::OpeningParenthesis, c),
+            ')' => self.push_single(Token::ClosingParenthesis, c),
+            '[' => self.push_single(Token::OpeningBracket, c),
+            ']' => self.push_single(Token::ClosingBracket, c),
+            '{' => self.push_single(Token::OpeningBrace, c),
+            '}' => self.push_single(Token::ClosingBrace, c),
+            ';' => self.push_single(Token::EndOfStatement, c),
+            ','|':'|'|'|'&'|'%'|'*'|'?' => self.push_single(Self::operator(c), c),
+            '+'|'-'|'/'|'!'|'>'|'<' => {
+                self.begin(TokenizerState::AmbiguousOperator);
+                self.buffer.push(c);
+
+                Ok(())
+            }
+            '=' => {
+                self.begin(TokenizerState::Assignment);
                 self.buffer.push(c);
 
                 Ok(())
             }
             _ => {
-                self.state = TokenizerState::SymbolOrKeyword;
+                self.begin(TokenizerState::SymbolOrKeyword);
                 self.buffer.push(c);
 
                 Ok(())
@@ -116,23 +272,19 @@ impl Tokenizer {
     fn push_ambiguous_state(&mut self, c: char) -> Result<()> {
         match c {
             c if c.is_whitespace() => self.save_token(),
-            c if c.is_numeric() => {
-                match self.save_token() {
-                    Ok(_) => {
-                        self.state = TokenizerState::Numeric;
-                        self.buffer.push(c);
-
-                        Ok(())
-                    }
-                    Err(e) => Err(e)
-                }
+            c if c.is_ascii_digit() => {
+                self.save_token()?;
+                self.begin(TokenizerState::Numeric);
+                self.buffer.push(c);
+
+                Ok(())
             }
 
             // "double" operators
-            '+'|'-'|'>'|'<'|'=' if self.buffer.ends_with(c) => {
+            '+'|'-'|'>'|'<' if self.buffer.ends_with(c) => {
                 self.buffer.push(c);
 
-                self.save_token()
+                self.end_token(c)
             }
 
             // comment "/*"
@@ -147,51 +299,127 @@ impl Tokenizer {
             '=' if ['!', '>', '<'].contains(&self.buffer.chars().next().unwrap()) => { // @todo do not use unwrap
                 self.buffer.push(c);
 
-                self.save_token()
+                self.end_token(c)
             }
 
             '"' => {
-                match self.save_token() {
-                    Ok(_) => Ok(self.state = TokenizerState::String),
-                    Err(e) => Err(e)
-                }
+                self.save_token()?;
+                self.begin(TokenizerState::String);
+
+                Ok(())
             }
             '\'' => {
-                match self.save_token() {
-                    Ok(_) => Ok(self.state = TokenizerState::Char),
-                    Err(e) => Err(e)
-                }
+                self.save_token()?;
+                self.begin(TokenizerState::Char);
+
+                Ok(())
             }
-            '(' => self.save_and_push(Token::OpeningParenthesis),
-            ')' => self.save_and_push(Token::ClosingParenthesis),
-            '[' => self.save_and_push(Token::OpeningBracket),
-            ']' => self.save_and_push(Token::ClosingBracket),
-            '{' => self.save_and_push(Token::OpeningBrace),
-            '}' => self.save_and_push(Token::ClosingBrace),
-            ';' => self.save_and_push(Token::EndOfStatement),
-            ','|':'|'|'|'&'|'%'|'?'|'!'|'*'|'>'|'<'|'+'|'-'|'/'|'=' => self.save_and_push(Token::Operator(String::from(c))),
+            '(' => self.save_and_push(Token::OpeningParenthesis, c),
+            ')' => self.save_and_push(Token::ClosingParenthesis, c),
+            '[' => self.save_and_push(Token::OpeningBracket, c),
+            ']' => self.save_and_push(Token::ClosingBracket, c),
+            '{' => self.save_and_push(Token::OpeningBrace, c),
+            '}' => self.save_and_push(Token::ClosingBrace, c),
+            ';' => self.save_and_push(Token::EndOfStatement, c),
+            ','|':'|'|'|'&'|'%'|'?'|'*' => self.save_and_push(Self::operator(c), c),
+            // may start another ambiguous operator, or a comment
             _ => {
                 self.save_token()
-                    .and_then(|_| self.push(c))
+                    .and_then(|_| self.push_state(c))
+            }
+        }
+    }
+
+    /**
+     * Parse an operator starting with "=" : simple assignment, compound assignment (like "=+" or "=<<"),
+     * or equality "=="
+     */
+    fn push_assignment_state(&mut self, c: char) -> Result<()> {
+        let complete = match (self.buffer.as_str(), c) {
+            ("=", '='|'!'|'<'|'>') => false,
+            ("=", '-'|'*'|'&'|'/') => self.dialect == Dialect::Historical,
+            ("=", '+'|'%'|'|') => true,
+            // with the modern dialect, "=/*" is an assignment followed by a comment
+            ("=/", '*') => {
+                let comment_start = self.start.next('=');
+
+                self.buffer.pop();
+                self.save_token_until(comment_start)?;
+                self.state = TokenizerState::Comment;
+                self.start = comment_start;
+
+                return Ok(());
+            },
+            ("==", '=') | ("=!", '=') | ("=<", '='|'<') | ("=>", '='|'>') => true,
+            _ => {
+                return self.save_assignment(!c.is_whitespace())
+                    .and_then(|_| self.push_state(c));
+            }
+        };
+
+        self.buffer.push(c);
+
+        if complete {
+            self.end_token(c)
+        } else {
+            Ok(())
+        }
+    }
+
+    /**
+     * Save the pending assignment operator
+     *
+     * An incomplete "=!" is always split into a simple assignment followed by the unary "!"
+     * With the modern dialect, "=-", "=*" and "=&" followed by an operand are split the same way,
+     * and "=/" is complete unless it starts a comment
+     */
+    fn save_assignment(&mut self, followed_by_operand: bool) -> Result<()> {
+        let split = match self.buffer.as_str() {
+            "=!" => true,
+            "=-"|"=*"|"=&" => followed_by_operand,
+            _ => false,
+        };
+
+        if !split {
+            return self.save_token();
+        }
+
+        let unary = self.buffer.pop().unwrap(); // buffer always contains 2 chars here
+        let unary_start = self.start.next('=');
+
+        self.save_token_until(unary_start)?;
+
+        match unary {
+            // may be followed by another operator like "--" or "!="
+            '-'|'!' => {
+                self.state = TokenizerState::AmbiguousOperator;
+                self.start = unary_start;
+                self.buffer.push(unary);
+            }
+            _ => {
+                let span = Span::new(unary_start, unary_start.next(unary));
+                self.emit(Spanned::new(Self::operator(unary), span));
             }
         }
+
+        Ok(())
     }
 
     fn push_symbol_or_keyword_state(&mut self, c: char) -> Result<()> {
         match c {
             c if c.is_whitespace() => self.save_token(),
 
-            '(' => self.save_and_push(Token::OpeningParenthesis),
-            ')' => self.save_and_push(Token::ClosingParenthesis),
-            '[' => self.save_and_push(Token::OpeningBracket),
-            ']' => self.save_and_push(Token::ClosingBracket),
-            '{' => self.save_and_push(Token::OpeningBrace),
-            '}' => self.save_and_push(Token::ClosingBrace),
-            ';' => self.save_and_push(Token::EndOfStatement),
+            '(' => self.save_and_push(Token::OpeningParenthesis, c),
+            ')' => self.save_and_push(Token::ClosingParenthesis, c),
+            '[' => self.save_and_push(Token::OpeningBracket, c),
+            ']' => self.save_and_push(Token::ClosingBracket, c),
+            '{' => self.save_and_push(Token::OpeningBrace, c),
+            '}' => self.save_and_push(Token::ClosingBrace, c),
+            ';' => self.save_and_push(Token::EndOfStatement, c),
 
             ','|':'|'|'|'&'|'%'|'?'|'!'|'*'|'>'|'<'|'+'|'-'|'/'|'=' => {
                 self.save_token()
-                    .and_then(|_| self.push(c)) // push from initial state
+                    .and_then(|_| self.push_state(c)) // push from initial state
             },
 
             _ => {
@@ -202,13 +430,15 @@ impl Tokenizer {
         }
     }
 
+    /**
+     * The buffer only keeps the last character when it's a '*', which may start the end of the comment
+     */
     fn push_comment_state(&mut self, c: char) -> Result<()> {
-        if c == '/' && self.buffer.ends_with('*') {
-            self.reset_state();
-        }
-
-        if c == '*' {
-            self.buffer.push(c);
+        match c {
+            '/' if self.buffer.ends_with('*') => self.reset_state(),
+            '*' if self.buffer.is_empty() => self.buffer.push(c),
+            '*' => {},
+            _ => self.buffer.clear(),
         }
 
         Ok(())
@@ -223,74 +453,195 @@ impl Tokenizer {
     }
 
     fn parse_string(&mut self, c: char, delimiter: char) -> Result<()> {
-        if c == delimiter {
-            return self.save_token();
-        }
+        if let Some(start) = self.escape.take() {
+            return match Self::escape_char(c) {
+                Some(escaped) => {
+                    self.buffer.push(escaped);
 
-        // @todo parse meta char
+                    Ok(())
+                }
+                None => Err(TokenError::new(
+                    "Unknown escape sequence",
+                    Span::new(start, self.position.next(c)),
+                    format!("*{}", c)
+                )),
+            };
+        }
 
-        self.buffer.push(c);
+        match c {
+            '*' => self.escape = Some(self.position),
+            c if c == delimiter => return self.end_token(c),
+            // B only allows newlines in char constants using the escape sequence "*n"
+            '\n' if delimiter == '\'' => {
+                return Err(TokenError::new(
+                    "Newline in character constant",
+                    Span::new(self.start, self.position),
+                    format!("'{}", self.buffer)
+                ));
+            }
+            _ => self.buffer.push(c),
+        }
 
         Ok(())
     }
 
+    /**
+     * Get the character represented by the escape sequence "*c"
+     * Returns None if the escape sequence is unknown
+     */
+    fn escape_char(c: char) -> Option<char> {
+        match c {
+            '0' => Some('\0'),
+            'e' => Some('\u{4}'), // end of file (EOT)
+            '(' => Some('{'),
+            ')' => Some('}'),
+            't' => Some('\t'),
+            '*' => Some('*'),
+            '\'' => Some('\''),
+            '"' => Some('"'),
+            'n' => Some('\n'),
+            _ => None,
+        }
+    }
+
     fn push_numeric_state(&mut self, c: char) -> Result<()> {
-        if c.is_numeric() {
-            self.buffer.push(c);
+        // a leading 0 means an octal literal
+        let octal = self.buffer.starts_with('0');
 
-            return Ok(());
+        match c {
+            '8'|'9' if octal => Err(self.invalid_digit(c, "Invalid digit in octal literal")),
+            c if c.is_ascii_digit() => {
+                self.buffer.push(c);
+
+                Ok(())
+            }
+            c if c.is_alphanumeric() || c == '_' => Err(self.invalid_digit(c, "Invalid digit in integer literal")),
+            _ => self.save_token().and_then(|_| self.push_state(c)),
         }
+    }
 
-        self.save_token().and_then(|_| self.push(c))
+    fn invalid_digit(&self, c: char, message: &'static str) -> TokenError {
+        TokenError::new(
+            message,
+            Span::new(self.start, self.position.next(c)),
+            format!("{}{}", self.buffer, c)
+        )
     }
 
-    fn save_and_push(&mut self, token: Token) -> Result<()> {
-        match self.save_token() {
-            Ok(_) => {
-                self.tokens.push(token);
+    /**
+     * Parse an integer literal
+     * If the literal starts with 0, it's parsed as octal, otherwise as decimal
+     * Octal literals may represent any bit pattern of the word, so they can go up to 037777777777
+     *
+     * Returns None on overflow
+     */
+    fn parse_integer(literal: &str) -> Option<i32> {
+        if literal.len() > 1 && literal.starts_with('0') {
+            u32::from_str_radix(literal, 8).ok().map(|value| value as i32)
+        } else {
+            literal.parse().ok()
+        }
+    }
 
-                Ok(())
-            },
-            Err(e) => Err(e)
+    /**
+     * Get the token of a single character operator
+     */
+    fn operator(c: char) -> Token {
+        match Operator::from_symbol(c.encode_utf8(&mut [0; 4])) {
+            Some(operator) => Token::Operator(operator),
+            None => unreachable!("{} is not an operator", c),
         }
     }
 
+    /**
+     * Start a new token at the current position
+     */
+    fn begin(&mut self, state: TokenizerState) {
+        self.state = state;
+        self.start = self.position;
+    }
+
+    /**
+     * Push a token made of the single current character
+     */
+    fn push_single(&mut self, token: Token, c: char) -> Result<()> {
+        let span = Span::new(self.position, self.position.next(c));
+        self.emit(Spanned::new(token, span));
+
+        Ok(())
+    }
+
+    fn save_and_push(&mut self, token: Token, c: char) -> Result<()> {
+        self.save_token()?;
+        self.push_single(token, c)
+    }
+
+    /**
+     * Save the current token, including the current character into its span
+     */
+    fn end_token(&mut self, c: char) -> Result<()> {
+        self.save_token_until(self.position.next(c))
+    }
+
+    /**
+     * Save the current token, ending before the current character
+     */
     fn save_token(&mut self) -> Result<()> {
-        match self.state {
-            TokenizerState::Empty => {}
-            TokenizerState::AmbiguousOperator => self.tokens.push(Token::Operator(self.buffer.clone())),
-            TokenizerState::SymbolOrKeyword => {
-                match self.buffer.as_str() {
-                    "auto" => self.tokens.push(Token::Auto),
-                    "extrn" => self.tokens.push(Token::Extern),
-                    "case" => self.tokens.push(Token::Case),
-                    "if" => self.tokens.push(Token::If),
-                    "else" => self.tokens.push(Token::Else),
-                    "while" => self.tokens.push(Token::While),
-                    "switch" => self.tokens.push(Token::Switch),
-                    "goto" => self.tokens.push(Token::Goto),
-                    "return" => self.tokens.push(Token::Return),
-                    _ => self.tokens.push(Token::Symbol(self.buffer.clone())),
+        self.save_token_until(self.position)
+    }
+
+    fn save_token_until(&mut self, end: Position) -> Result<()> {
+        let span = Span::new(self.start, end);
+
+        let token = match self.state {
+            TokenizerState::Empty => None,
+            TokenizerState::AmbiguousOperator | TokenizerState::Assignment => {
+                match Operator::from_symbol(self.buffer.as_str()) {
+                    Some(operator) => Some(Token::Operator(operator)),
+                    None => return Err(TokenError::new("Invalid operator", span, self.buffer.clone())),
                 }
             }
-            TokenizerState::Comment => {}
+            TokenizerState::SymbolOrKeyword => {
+                Some(match self.buffer.as_str() {
+                    "auto" => Token::Auto,
+                    "extrn" => Token::Extern,
+                    "case" => Token::Case,
+                    "if" => Token::If,
+                    "else" => Token::Else,
+                    "while" => Token::While,
+                    "switch" => Token::Switch,
+                    "goto" => Token::Goto,
+                    "return" => Token::Return,
+                    _ => Token::Symbol(self.symbols.intern(self.buffer.as_str())),
+                })
+            }
+            TokenizerState::Comment => None,
             TokenizerState::Char => {
                 let mut chars: [char; 4] = ['\0'; 4];
-
-                if self.buffer.len() < 1 || self.buffer.len() > 4 {
-                    return Err(TokenError::new("Invalid character size : must be between 1 and 4"));
+                let size = self.buffer.chars().count();
+
+                if !(1..=4).contains(&size) {
+                    return Err(TokenError::new(
+                        "Invalid character size : must be between 1 and 4",
+                        span,
+                        format!("'{}'", self.buffer)
+                    ));
                 }
 
                 self.buffer.chars().enumerate().for_each(|(i, c)| chars[i] = c);
-                self.tokens.push(Token::Char(chars));
+                Some(Token::Char(chars))
             }
-            TokenizerState::String => self.tokens.push(Token::String(self.buffer.clone())),
+            TokenizerState::String => Some(Token::String(self.buffer.clone())),
             TokenizerState::Numeric => {
-                match self.buffer.parse() {
-                    Ok(i) => self.tokens.push(Token::Integer(i)),
-                    Err(_) => return Err(TokenError::new("Error during parsing number token"))
+                match Self::parse_integer(self.buffer.as_str()) {
+                    Some(i) => Some(Token::Integer(i)),
+                    None => return Err(TokenError::new("Integer literal overflow", span, self.buffer.clone()))
                 }
             }
+        };
+
+        if let Some(token) = token {
+            self.emit(Spanned::new(token, span));
         }
 
         self.reset_state();
@@ -303,3 +654,9 @@ impl Tokenizer {
         self.buffer.clear();
     }
 }
+
+impl Default for Tokenizer {
+    fn default() -> Self {
+        Tokenizer::new()
+    }
+}
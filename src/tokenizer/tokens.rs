@@ -1,7 +1,12 @@
+use std::fmt::{Display, Formatter};
+
+use crate::tokenizer::{Operator, Symbol};
+
 /**
  * All units of the code
+ * Names are interned, but string literals are owned by their token, so they are freed with the program
  */
-#[derive(Debug, Clone, PartialEq)] // @todo use Copy instead of clone
+#[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     /**
      * Represent any symbol, like function name,
@@ -10,7 +15,7 @@ pub enum Token {
      *
      * Note: symbol are distinct from keyword, where each keyword has its own token
      */
-    Symbol(String),
+    Symbol(Symbol),
 
     /**
      * Represent a constant integer value
@@ -21,6 +26,7 @@ pub enum Token {
      * Represent a "character" token (i.e. value wrapped between single quote)
      * Unlike C like language, in this value allows to have multiple chars,
      * because it uses a word to store this value, so it can store at most 4 chars into it
+     * (only 2 are kept with 16 bits words, see layout::WordSize)
      * When encounter less than 4 chars, other values will be filled with NULL (0)
      * At least one char is required.
      */
@@ -28,16 +34,15 @@ pub enum Token {
 
     /**
      * Represent a string token (i.e. value wrapped between single quote)
-     * The value of this token doesn't include the termination character (0x05)
+     * The value of this token doesn't include the termination character (0x04), and escape sequences are resolved
      */
     String(String),
 
     /**
-     * Represent an operator, can be unary, or binary.
-     * The value is the operator string. A string is used because some operators can have multiple
-     * characters, like increment "++"
+     * Represent an operator, can be unary, binary, or an assignment.
+     * Assignment operators are "=" or a binary operator prefixed by "=", like "=+" or "=<<".
      */
-    Operator(String),
+    Operator(Operator),
 
     /**
      * Represent the end of a statement (character ;)
@@ -120,18 +125,81 @@ pub enum Token {
     Return,
 }
 
-impl Token {
-    pub fn is_operator(&self, c: char) -> bool {
+impl Display for Token {
+    /**
+     * Display the token as written into the source code
+     */
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Token::Operator(name) => name.len() == 1 && name.starts_with(c),
-            _ => false,
+            Token::Symbol(name) => write!(f, "{}", name),
+            Token::Integer(value) => write!(f, "{}", value),
+            Token::Char(chars) => {
+                // the trailing nulls only fill the constant, but a null character is kept
+                let size = chars.iter().rposition(|c| *c != '\0').map(|last| last + 1).unwrap_or(1);
+
+                write!(f, "{}", quote(chars[..size].iter().copied(), '\''))
+            },
+            Token::String(value) => write!(f, "{}", quote(value.chars(), '"')),
+            Token::Operator(op) => write!(f, "{}", op),
+            Token::EndOfStatement => write!(f, ";"),
+            Token::OpeningBrace => write!(f, "{{"),
+            Token::ClosingBrace => write!(f, "}}"),
+            Token::OpeningParenthesis => write!(f, "("),
+            Token::ClosingParenthesis => write!(f, ")"),
+            Token::OpeningBracket => write!(f, "["),
+            Token::ClosingBracket => write!(f, "]"),
+            Token::Auto => write!(f, "auto"),
+            Token::Extern => write!(f, "extrn"),
+            Token::Case => write!(f, "case"),
+            Token::If => write!(f, "if"),
+            Token::Else => write!(f, "else"),
+            Token::While => write!(f, "while"),
+            Token::Switch => write!(f, "switch"),
+            Token::Goto => write!(f, "goto"),
+            Token::Return => write!(f, "return"),
         }
     }
+}
+
+impl Token {
+    pub fn is_operator(&self, operator: Operator) -> bool {
+        *self == Token::Operator(operator)
+    }
 
-    pub fn is_operator_str(&self, str: &str) -> bool {
+    /**
+     * Check if the token is a simple or compound assignment operator
+     * Note: "==" is the equality operator, but "===" is the compound assignment of equality
+     */
+    pub fn is_assignment(&self) -> bool {
         match self {
-            Token::Operator(name) => name == str,
+            Token::Operator(operator) => operator.is_assignment(),
             _ => false,
         }
     }
 }
+
+/**
+ * Write the characters of a string or char constant between the delimiters,
+ * using the escape sequences resolved by the tokenizer when needed
+ */
+pub fn quote<I: IntoIterator<Item = char>>(chars: I, delimiter: char) -> String {
+    let mut quoted = String::from(delimiter);
+
+    for c in chars {
+        match c {
+            '\0' => quoted.push_str("*0"),
+            '\u{4}' => quoted.push_str("*e"),
+            '\t' => quoted.push_str("*t"),
+            '\n' => quoted.push_str("*n"),
+            '*' => quoted.push_str("**"),
+            c if c == delimiter => {
+                quoted.push('*');
+                quoted.push(c);
+            },
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push(delimiter);
+    quoted
+}
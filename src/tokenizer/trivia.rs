@@ -0,0 +1,200 @@
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+
+use crate::tokenizer::{Position, Span, Spanned, Token, Tokenizer};
+
+/**
+ * Source text which is not part of a token
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Trivia {
+    /**
+     * Consecutive whitespace characters, including new lines
+     */
+    Whitespace(String),
+
+    /**
+     * A comment, including its delimiters "/*" and "*/"
+     */
+    Comment(String),
+}
+
+impl Trivia {
+    /**
+     * The source text of the trivia
+     */
+    pub fn text(&self) -> &str {
+        match self {
+            Trivia::Whitespace(text) => text,
+            Trivia::Comment(text) => text,
+        }
+    }
+}
+
+/**
+ * A token with its exact source text, and the trivia preceding it
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct LosslessToken {
+    pub leading: Vec<Spanned<Trivia>>,
+    pub token: Spanned<Token>,
+    /**
+     * The token as written into the source, like "017" for the integer 15
+     */
+    pub text: String,
+}
+
+/**
+ * All the tokens and trivia of a source
+ * Displaying it reproduces the source byte for byte
+ */
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LosslessTokens {
+    pub tokens: Vec<LosslessToken>,
+    /**
+     * Trivia following the last token
+     */
+    pub trailing: Vec<Spanned<Trivia>>,
+}
+
+impl Display for LosslessTokens {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for token in &self.tokens {
+            for trivia in &token.leading {
+                write!(f, "{}", trivia.value.text())?;
+            }
+
+            write!(f, "{}", token.text)?;
+        }
+
+        for trivia in &self.trailing {
+            write!(f, "{}", trivia.value.text())?;
+        }
+
+        Ok(())
+    }
+}
+
+/**
+ * Keep the source text consumed by the tokenizer, to split it into tokens and trivia
+ */
+#[derive(Default)]
+pub(super) struct TriviaCollector {
+    /**
+     * The source text since the end of the last token
+     */
+    source: String,
+    /**
+     * Position of the first character of source
+     */
+    start: Position,
+    /**
+     * Leading trivia and source text of the completed tokens, in the same order
+     */
+    completed: VecDeque<(Vec<Spanned<Trivia>>, String)>,
+    trailing: Vec<Spanned<Trivia>>,
+}
+
+impl TriviaCollector {
+    pub(super) fn new() -> TriviaCollector {
+        TriviaCollector {
+            start: Position::start(),
+            ..TriviaCollector::default()
+        }
+    }
+
+    pub(super) fn push(&mut self, c: char) {
+        self.source.push(c);
+    }
+
+    /**
+     * Take the source text until the end of the completed token
+     * Tokens must be completed in the source order
+     */
+    pub(super) fn complete(&mut self, span: Span) {
+        let token_start = span.start.offset - self.start.offset;
+        let token_end = span.end.offset - self.start.offset;
+
+        let leading = Self::split(&self.source[..token_start], self.start);
+        let text = self.source[token_start..token_end].to_string();
+
+        self.completed.push_back((leading, text));
+        self.source.drain(..token_end);
+        self.start = span.end;
+    }
+
+    /**
+     * Drop the trivia of the next completed token
+     */
+    pub(super) fn skip(&mut self) {
+        self.completed.pop_front();
+    }
+
+    /**
+     * All the remaining source is trivia
+     */
+    pub(super) fn finalize(&mut self) {
+        self.trailing = Self::split(&self.source, self.start);
+        self.source.clear();
+    }
+
+    /**
+     * Split the text between two tokens into comments and whitespaces
+     */
+    fn split(mut text: &str, mut position: Position) -> Vec<Spanned<Trivia>> {
+        let mut trivia = Vec::new();
+
+        while !text.is_empty() {
+            let (length, item) = if let Some(comment) = text.strip_prefix("/*") {
+                let length = comment.find("*/").map(|end| end + 4).unwrap_or(text.len());
+
+                (length, Trivia::Comment(text[..length].to_string()))
+            } else {
+                let length = text.find("/*").unwrap_or(text.len());
+
+                (length, Trivia::Whitespace(text[..length].to_string()))
+            };
+
+            let end = text[..length].chars().fold(position, |position, c| position.next(c));
+
+            trivia.push(Spanned::new(item, Span::new(position, end)));
+            text = &text[length..];
+            position = end;
+        }
+
+        trivia
+    }
+}
+
+impl Tokenizer {
+    /**
+     * Take the next completed token, with its trivia and source text
+     * Without trivia mode (see with_trivia()), the trivia are empty, and the text is the displayed token
+     */
+    pub fn next_lossless_token(&mut self) -> Option<LosslessToken> {
+        let leading = self.trivia.as_mut().and_then(|trivia| trivia.completed.pop_front());
+        let token = self.tokens.pop_front()?;
+
+        Some(match leading {
+            Some((leading, text)) => LosslessToken { leading, token, text },
+            None => LosslessToken { leading: Vec::new(), text: token.value.to_string(), token },
+        })
+    }
+
+    /**
+     * Get all the completed tokens, with the trivia of the source
+     * Must be called after finalize() to get the trailing trivia
+     */
+    pub fn into_lossless_tokens(mut self) -> LosslessTokens {
+        let mut tokens = Vec::with_capacity(self.tokens.len());
+
+        while let Some(token) = self.next_lossless_token() {
+            tokens.push(token);
+        }
+
+        LosslessTokens {
+            tokens,
+            trailing: self.trivia.map(|trivia| trivia.trailing).unwrap_or_default(),
+        }
+    }
+}
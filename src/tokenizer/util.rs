@@ -1,40 +1,189 @@
-use std::error;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
 use std::fs::File;
-use std::io::Read;
+use std::io::{self, ErrorKind, Read};
 use std::path::Path;
-use crate::tokenizer::Tokenizer;
+use crate::tokenizer::{Position, TokenError, Tokenizer};
 
-impl Tokenizer {
-    pub fn from_file(file: &Path) -> Result<Tokenizer, Box<dyn error::Error>> {
-        let mut file = match File::open(file) {
-            Ok(file) => file,
-            Err(e) => return Err(e.into())
-        };
+const CHUNK_SIZE: usize = 4096;
+
+/**
+ * Error raised when tokenizing a source read from a file or a reader
+ */
+#[derive(Debug)]
+pub enum ReadError {
+    Io(io::Error),
+    /**
+     * Invalid UTF-8 encoding, or invalid token
+     */
+    Token(TokenError),
+}
+
+impl Display for ReadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadError::Io(e) => write!(f, "{}", e),
+            ReadError::Token(e) => write!(f, "{}", e),
+        }
+    }
+}
 
-        let mut buf: [u8; 256] = [0; 256];
+impl Error for ReadError {
+
+}
+
+impl From<io::Error> for ReadError {
+    fn from(e: io::Error) -> Self {
+        ReadError::Io(e)
+    }
+}
+
+impl From<TokenError> for ReadError {
+    fn from(e: TokenError) -> Self {
+        ReadError::Token(e)
+    }
+}
+
+impl Tokenizer {
+    /**
+     * Tokenize the whole source code
+     */
+    pub fn from_string(source: &str) -> Result<Tokenizer, TokenError> {
         let mut tokenizer = Tokenizer::new();
 
+        for c in source.chars() {
+            tokenizer.push(c)?;
+        }
+
+        tokenizer.finalize()?;
+
+        Ok(tokenizer)
+    }
+
+    /**
+     * Tokenize the whole UTF-8 file, using this tokenizer configuration
+     */
+    pub fn read_file(self, file: &Path) -> Result<Tokenizer, ReadError> {
+        self.read_to_end(File::open(file)?)
+    }
+
+    /**
+     * Tokenize the whole UTF-8 source given on stdin, using this tokenizer configuration
+     */
+    pub fn read_stdin(self) -> Result<Tokenizer, ReadError> {
+        self.read_to_end(io::stdin().lock())
+    }
+
+    /**
+     * Tokenize the UTF-8 source code read until the end of the reader, using this tokenizer configuration
+     */
+    pub fn read_to_end<R: Read>(mut self, reader: R) -> Result<Tokenizer, ReadError> {
+        let mut decoder = Decoder::new(reader);
+
+        while let Some(chunk) = decoder.read_chunk()? {
+            for c in chunk.chars() {
+                self.push(c)?;
+            }
+        }
+
+        self.finalize()?;
+
+        Ok(self)
+    }
+}
+
+/**
+ * Read the whole UTF-8 source code, without tokenizing it
+ * Invalid encoding is reported the same way as by the tokenizer
+ */
+pub fn read_source<R: Read>(reader: R) -> Result<String, ReadError> {
+    let mut decoder = Decoder::new(reader);
+    let mut source = String::new();
+
+    while let Some(chunk) = decoder.read_chunk()? {
+        source.push_str(chunk);
+    }
+
+    Ok(source)
+}
+
+/**
+ * Decode the UTF-8 bytes of a reader by chunks
+ */
+pub(super) struct Decoder<R> {
+    reader: R,
+    /**
+     * Bytes read but not decoded yet : a character may be split between two reads
+     */
+    pending: Vec<u8>,
+    /**
+     * The last decoded chunk
+     */
+    chunk: String,
+    /**
+     * Position following the last decoded character
+     */
+    position: Position,
+    /**
+     * Invalid encoding found after the last decoded chunk, returned by the next read
+     */
+    error: Option<TokenError>,
+}
+
+impl<R: Read> Decoder<R> {
+    pub(super) fn new(reader: R) -> Decoder<R> {
+        Decoder {
+            reader,
+            pending: Vec::with_capacity(CHUNK_SIZE),
+            chunk: String::new(),
+            position: Position::start(),
+            error: None,
+        }
+    }
+
+    /**
+     * Read until the end of the reader, and get the next decoded characters
+     * Returns None at the end of the reader
+     */
+    pub(super) fn read_chunk(&mut self) -> Result<Option<&str>, ReadError> {
+        if let Some(error) = self.error.take() {
+            return Err(error.into());
+        }
+
+        let mut buf = [0u8; CHUNK_SIZE];
+
         loop {
-            let size = match file.read(buf.as_mut()) {
+            let size = match self.reader.read(&mut buf) {
+                Ok(0) if self.pending.is_empty() => return Ok(None),
+                // the last character is not complete
+                Ok(0) => return Err(TokenError::invalid_encoding(self.position, &self.pending).into()),
                 Ok(size) => size,
-                Err(e) => return Err(e.into())
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
             };
 
-            for i in 0..size {
-                match tokenizer.push(char::from(buf[i])) {
-                    Ok(_) => {}
-                    Err(e) => return Err(e.into())
-                }
-            }
+            self.pending.extend_from_slice(&buf[..size]);
+
+            let (valid, invalid) = match std::str::from_utf8(&self.pending) {
+                Ok(chunk) => (chunk.len(), None),
+                // error_len() is None when the sequence is incomplete : wait for the next read
+                Err(e) => (e.valid_up_to(), e.error_len()),
+            };
 
-            if size < 256 {
-                break
+            self.chunk.clear();
+            // the bytes are already checked, so this conversion cannot fail
+            self.chunk.push_str(std::str::from_utf8(&self.pending[..valid]).unwrap_or_default());
+            self.position = self.chunk.chars().fold(self.position, |position, c| position.next(c));
+            self.pending.drain(..valid);
+
+            if let Some(size) = invalid {
+                // the valid characters are returned first, so the errors are reported in the source order
+                self.error = Some(TokenError::invalid_encoding(self.position, &self.pending[..size]));
             }
-        }
 
-        match tokenizer.finalize() {
-            Ok(_) => Ok(tokenizer),
-            Err(e) => Err(e.into())
+            if !self.chunk.is_empty() || self.error.is_some() {
+                return Ok(Some(&self.chunk));
+            }
         }
     }
 }
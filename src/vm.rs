@@ -0,0 +1,347 @@
+pub use error::RuntimeError;
+pub use host::{FromWord, Host, HostFunction, IntoWord};
+pub use library::library_names;
+pub use machine::{Machine, NativeFunction};
+pub use memory::Memory;
+
+mod error;
+mod host;
+mod library;
+mod machine;
+mod memory;
+
+#[cfg(test)]
+mod test {
+    use std::fmt::Debug;
+
+    use crate::layout::WordSize;
+    use crate::lowering::lower;
+    use crate::resolver::Resolver;
+    use crate::vm::{library_names, Host, Machine, Memory, RuntimeError};
+
+    /**
+     * Run the main function, and get its result and output
+     */
+    fn run(source: &str) -> (Result<i64, RuntimeError>, String) {
+        run_with_input(source, "")
+    }
+
+    fn run_with_input(source: &str, input: &str) -> (Result<i64, RuntimeError>, String) {
+        let mut output = Vec::new();
+        let result = Machine::new(&crate::compile(source).unwrap()).unwrap()
+            .with_input(input.as_bytes())
+            .with_output(&mut output)
+            .run();
+
+        (result, String::from_utf8(output).unwrap())
+    }
+
+    /**
+     * Get the message of an execution failing with an error, which is not an exit
+     */
+    fn failure<T: Debug>(result: Result<T, RuntimeError>) -> String {
+        let error = result.unwrap_err();
+
+        assert!(!error.is_exit(), "{}", error);
+
+        error.message().to_string()
+    }
+
+    fn run_value(source: &str) -> i64 {
+        run(source).0.unwrap()
+    }
+
+    #[test]
+    fn run_printn() {
+        let source = std::fs::read_to_string("example/printn.b").unwrap();
+
+        assert_eq!((Ok(0), String::from("1234\n")), run(&source));
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(7, run_value("main() return (1 + 2 * 3);"));
+        assert_eq!(-3, run_value("main() return (-7 / 2);"));
+        assert_eq!(1, run_value("main() return (7 % 3 == 1 & 2 < 3);"));
+        assert_eq!(20, run_value("main() return (5 << 2 | 0 >> 1);"));
+        assert_eq!(1, run_value("main() return (!0 ? -(-1) : 2);"));
+    }
+
+    #[test]
+    fn variables() {
+        assert_eq!(8, run_value("main() { auto a, b; a = 3; b = a++ + ++a; a =+ b - 1; return (a - b + 4); }"));
+        assert_eq!(6, run_value("main() { auto v 3, p, i; i = 0; while (i < 3) v[i] = ++i; p = &v[1]; return (*p + p[1] + v[0] + *v - 1); }"));
+        assert_eq!(42, run_value("x 40, 2; v[] 1, 2; main() { extrn x, v; return (x + (&x)[1] + v[1] - 2); }"));
+        assert_eq!(5, run_value("p q; q 5; main() { extrn p; return (*p); }"));
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(120, run_value("fact(n) return (n <= 1 ? 1 : n * fact(n - 1)); main() return (fact(5));"));
+        assert_eq!(3, run_value("add(a, b) return (a + b); main() { extrn add; auto f; f = add; return (f(1, 2)); }"));
+        assert_eq!(0, run_value("missing(a, b) return (b); main() return (missing(1));"));
+        assert_eq!(10, run_value("t[] f; f(a) return (a * 2); main() { extrn t; return (t[0](5)); }"));
+    }
+
+    #[test]
+    fn control_flow() {
+        let source = "classify(c) { switch (c) { case 'a': return (1); case 2: c = 10; case 3: return (c); } return (-1); }";
+
+        assert_eq!(1, run_value(&format!("{} main() return (classify('a'));", source)));
+        assert_eq!(10, run_value(&format!("{} main() return (classify(2));", source)));
+        assert_eq!(3, run_value(&format!("{} main() return (classify(3));", source)));
+        assert_eq!(-1, run_value(&format!("{} main() return (classify(4));", source)));
+        assert_eq!(45, run_value("main() { auto i, s, l; i = s = 0; l = loop; loop: s =+ i; if (++i < 10) goto l; return (s); }"));
+    }
+
+    #[test]
+    fn putchar_and_strings() {
+        let source = "main() { extrn putchar; auto s; s = \"ab\"; putchar('hi'); putchar(' '); putchar(*s & 0377); putchar('*n'); }";
+
+        assert_eq!((Ok(0), String::from("hi a\n")), run(source));
+    }
+
+    #[test]
+    fn library_input_output() {
+        let source = "main() { extrn getchar, putchar; auto c; while ((c = getchar()) != '*e') putchar(c == ' ' ? '_' : c); }";
+
+        assert_eq!((Ok(0), String::from("echo_me\n")), run_with_input(source, "echo me\n"));
+
+        let source = "main() { extrn printf; printf(\"%d %o %c %s%% %x*n\", -42, 8, 'ok', \"str\"); }";
+
+        assert_eq!((Ok(0), String::from("-42 10 ok str% %x\n")), run(source));
+    }
+
+    #[test]
+    fn library_strings_and_vectors() {
+        let source = "main() { extrn char, lchar, printf; auto s; s = \"abc\"; lchar(s, 1, 'X'); printf(\"%s %c\", s, char(s, 2)); }";
+
+        assert_eq!((Ok(0), String::from("aXc c")), run(source));
+
+        let source = "main() { extrn getvec, rlsevec; auto v, w; v = getvec(9); v[9] = 5; rlsevec(v, 9); w = getvec(3); return ((v == w) + w[3]); }";
+
+        assert_eq!(1, run_value(source));
+        assert_eq!(
+            "Invalid release of the address 12 in function `main`",
+            failure(run("main() { extrn rlsevec; rlsevec(12, 1); }").0)
+        );
+        assert_eq!(
+            "Invalid release of the address 16777206 in function `main`",
+            failure(run("main() { extrn getvec, rlsevec; auto v; v = getvec(9); rlsevec(v, 9); rlsevec(v, 9); }").0)
+        );
+        assert_eq!(
+            "Invalid release of 5 words at the address 16777206, allocated with 10 words in function `main`",
+            failure(run("main() { extrn getvec, rlsevec; rlsevec(getvec(9), 4); }").0)
+        );
+    }
+
+    #[test]
+    fn library_calls() {
+        assert_eq!(20, run_value("f(a, b, c) { extrn nargs; return (nargs()); } main() return (f(1, 2) * 10 + f());"));
+        assert_eq!(
+            (Ok(0), String::from("a")),
+            run("f() { extrn exit; exit(); } main() { extrn putchar; putchar('a'); f(); putchar('b'); return (5); }")
+        );
+    }
+
+    fn run_words(source: &str, word_size: WordSize) -> (Result<i64, RuntimeError>, String) {
+        let mut output = Vec::new();
+        let result = Machine::with_word_size(&crate::compile(source).unwrap(), word_size).unwrap().with_output(&mut output).run();
+
+        (result, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn word_sizes() {
+        let source = "main() return (32767 + 1);";
+
+        assert_eq!(Ok(-32768), run_words(source, WordSize::Bits16).0);
+        assert_eq!(Ok(32768), run_words(source, WordSize::Bits32).0);
+        assert_eq!(Ok(-2147483648), run_words("main() return (017777777777 + 1);", WordSize::Bits32).0);
+        assert_eq!(Ok(0), run_words("main() return (1 << 16);", WordSize::Bits16).0);
+        assert_eq!(Ok(-1), run_words("main() return (-1 >> 4);", WordSize::Bits16).0);
+
+        let source = "main() { extrn printf, putchar; auto s; s = \"hello\"; printf(\"%o %d %c \", -1, *s, 'ab'); putchar('é'); putchar('*n'); }";
+
+        assert_eq!((Ok(0), String::from("177777 25960 ab é\n")), run_words(source, WordSize::Bits16));
+        assert_eq!((Ok(0), String::from("37777777777 1819043176 ab é\n")), run_words(source, WordSize::Bits32));
+
+        // the case constants are wrapped like the switch value
+        let source = "main() { switch (-1) case 0177777: return (1); return (2); }";
+
+        assert_eq!(Ok(1), run_words(source, WordSize::Bits16).0);
+        assert_eq!(Ok(2), run_words(source, WordSize::Bits32).0);
+    }
+
+    #[test]
+    fn packed_strings_on_pdp11() {
+        let source = "main() { extrn char, lchar, printf; auto s, i; s = \"hello\"; i = 0; while (char(s, i) != '*e') lchar(s, i, char(s, i++) - 040); printf(\"%s %d\", s, s[2]); }";
+
+        assert_eq!((Ok(0), String::from("HELLO 1103")), run_words(source, WordSize::Bits16));
+        assert_eq!(
+            "Out of memory in function `main`",
+            failure(run_words("main() { extrn getvec; getvec(20000); getvec(20000); }", WordSize::Bits16).0)
+        );
+        assert_eq!(
+            "Out of memory in function `f`",
+            failure(run_words("f(n) { auto v 100; f(n + 1); } main() f(0);", WordSize::Bits16).0)
+        );
+    }
+
+    #[test]
+    fn runtime_errors() {
+        let errors = [
+            ("main() return (1 / 0);", "Division by zero in function `main`"),
+            ("main() return (*0);", "Invalid memory access at address 0 in function `main`"),
+            ("main() { auto x; x = 1000; x(); }", "Call of the value 1000 which is not a function in function `main`"),
+            ("main() main();", "Stack overflow in function `main`"),
+            ("main() { auto l; l = 100; goto l; }", "Invalid instruction index 100 in function `main`"),
+        ];
+
+        for (source, message) in errors {
+            assert_eq!(message, failure(run(source).0), "{}", source);
+        }
+
+        assert_eq!(
+            "Undefined function `main`",
+            failure(Machine::new(&crate::compile("f();").unwrap()).unwrap().run())
+        );
+    }
+
+    #[test]
+    fn packed_strings() {
+        let mut memory = Memory::default();
+        let address = memory.allocate_string("hello, world").unwrap() as i64;
+
+        assert_eq!(2, memory.len() - address as usize);
+        assert_eq!(b'h', memory.char_at(address, 0).unwrap());
+        assert_eq!(b'w', memory.char_at(address, 7).unwrap());
+        assert_eq!(4, memory.char_at(address, 12).unwrap());
+
+        memory.set_char(address, 8, b'W').unwrap();
+        assert_eq!(b'W', memory.char_at(address, 8).unwrap());
+        assert_eq!(b'r', memory.char_at(address, 9).unwrap());
+    }
+
+    #[test]
+    fn heap_releases() {
+        let mut memory = Memory::default();
+        let vector = memory.allocate_heap(4).unwrap() as i64;
+        let other = memory.allocate_heap(2).unwrap() as i64;
+
+        assert_eq!("Invalid release of 3 words at the address 16777212, allocated with 4 words", failure(memory.release_heap(vector, 3)));
+        assert_eq!("Invalid release of the address 16777213", failure(memory.release_heap(vector + 1, 3)));
+        assert_eq!(Ok(()), memory.release_heap(vector, 4));
+        assert_eq!("Invalid release of the address 16777212", failure(memory.release_heap(vector, 4)));
+
+        // the released block is reused, while the other one is kept
+        assert_eq!(vector, memory.allocate_heap(4).unwrap() as i64);
+        assert_eq!(Ok(()), memory.release_heap(other, 2));
+    }
+
+    fn run_host(source: &str, host: Host) -> Result<i64, RuntimeError> {
+        let mut program = crate::parse(source).unwrap();
+        let resolution = Resolver::new().with_externals(library_names()).with_externals(host.names()).resolve(&program).unwrap();
+
+        lower(&mut program, &resolution);
+
+        Machine::with_host(&program, WordSize::default(), host)?.run()
+    }
+
+    #[test]
+    fn host_functions() {
+        let mut calls = Vec::new();
+        let host = Host::new().function("record", |_, arguments| {
+            calls.push(arguments.to_vec());
+            Ok(arguments.len() as i64)
+        });
+
+        assert_eq!(Ok(2), run_host("main() { extrn record; record(1); return (record(2, 3) + record()); }", host));
+        assert_eq!(vec![vec![1], vec![2, 3], vec![]], calls);
+    }
+
+    #[test]
+    fn host_globals() {
+        let host = Host::new()
+            .global("base", 40)
+            .vector("table", vec![1, 2, 3]);
+
+        assert_eq!(Ok(45), run_host("main() { extrn base, table; base =+ table[1]; return (base + table[2]); }", host));
+    }
+
+    #[test]
+    fn host_conversions() {
+        let host = Host::new()
+            .function("greet", |machine, arguments| {
+                let name: String = machine.from_word(arguments[0])?;
+                machine.to_word(format!("hello, {}", name))
+            })
+            .function("sum", |machine, arguments| {
+                let size: usize = machine.from_word(arguments[1])?;
+                Ok(machine.read_vector(arguments[0], size)?.iter().sum())
+            });
+        let source = "main() {
+            extrn greet, sum, char;
+            auto s, v 3;
+            s = greet(\"world\");
+            v[0] = char(s, 0); v[1] = char(s, 7); v[2] = char(s, 11);
+            return (sum(v, 3));
+        }";
+
+        assert_eq!(Ok(b'h' as i64 + b'w' as i64 + b'd' as i64), run_host(source, host));
+    }
+
+    #[test]
+    fn host_string_release() {
+        let host = || Host::new()
+            .function("name", |machine, _| machine.to_word("name"))
+            .function("forget", |machine, arguments| machine.release_string(arguments[0]).map(|_| 0));
+        let source = "main() { extrn name, forget; auto s, t; s = name(); forget(s); t = name(); return (s == t); }";
+
+        // the released words are reused by the next string
+        assert_eq!(Ok(1), run_host(source, host()));
+        assert_eq!(
+            "Invalid release of the address 16777215 in function `main`",
+            failure(run_host("main() { extrn name, forget; auto s; s = name(); forget(s); forget(s); }", host()))
+        );
+    }
+
+    #[test]
+    fn host_callbacks() {
+        let host = Host::new().function("apply", |machine, arguments| machine.call("square", &arguments[..1]));
+
+        assert_eq!(Ok(49), run_host("square(x) return (x * x); main() { extrn apply; return (apply(7)); }", host));
+
+        let host = Host::new().function("again", |machine, _| machine.call("main", &[]));
+
+        assert_eq!(
+            "Recursive call of a host function in function `main`",
+            failure(run_host("main() { extrn again; return (again()); }", host))
+        );
+
+        // exit unwinds the host function too
+        let mut exited = false;
+        let host = Host::new().function("attempt", |machine, _| {
+            let result = machine.call("quit", &[]);
+            exited = result.as_ref().is_err_and(RuntimeError::is_exit);
+            result
+        });
+
+        assert_eq!(Ok(0), run_host("quit() { extrn exit; exit(); } main() { extrn attempt; attempt(); return (1); }", host));
+        assert!(exited);
+    }
+
+    #[test]
+    fn host_duplicate_names() {
+        let host = Host::new().global("main", 1);
+
+        assert_eq!(
+            "The host defines `main`, which is already defined",
+            failure(run_host("main() return (0);", host))
+        );
+
+        let host = Host::new().function("putchar", |_, _| Ok(7));
+
+        assert_eq!(Ok(7), run_host("main() { extrn putchar; return (putchar('a')); }", host));
+    }
+}
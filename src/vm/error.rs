@@ -0,0 +1,72 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/**
+ * Error raised while executing a program
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    message: String,
+    /**
+     * The program called exit, it's not a failure
+     */
+    exit: bool,
+    /**
+     * The message already names the function where the error occurred
+     */
+    located: bool,
+}
+
+impl RuntimeError {
+    pub fn new(message: String) -> RuntimeError {
+        RuntimeError {
+            message,
+            exit: false,
+            located: false,
+        }
+    }
+
+    /**
+     * Create the error unwinding all calls when the program calls exit
+     */
+    pub fn exit() -> RuntimeError {
+        RuntimeError {
+            message: String::from("The program exited"),
+            exit: true,
+            located: false,
+        }
+    }
+
+    pub fn is_exit(&self) -> bool {
+        self.exit
+    }
+
+    pub fn message(&self) -> &str {
+        self.message.as_str()
+    }
+
+    /**
+     * Name the function where the error occurred, unless it's already named by a nested call
+     */
+    pub(super) fn in_function(self, name: &str) -> RuntimeError {
+        if self.exit || self.located {
+            return self;
+        }
+
+        RuntimeError {
+            message: format!("{} in function `{}`", self.message, name),
+            exit: false,
+            located: true,
+        }
+    }
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RuntimeError: {}", self.message)
+    }
+}
+
+impl Error for RuntimeError {
+
+}
@@ -0,0 +1,187 @@
+use crate::tokenizer::Symbol;
+use crate::vm::{Machine, RuntimeError};
+
+type Result<T> = std::result::Result<T, RuntimeError>;
+
+/**
+ * A function provided by the application, called with the machine and the argument words
+ */
+pub type HostFunction<'a> = Box<dyn FnMut(&mut Machine<'a>, &[i64]) -> Result<i64> + 'a>;
+
+/**
+ * A global provided by the application
+ */
+pub(super) enum HostGlobal {
+    Word(i64),
+    /**
+     * A word pointing to the following words, like the vectors of the program
+     */
+    Vector(Vec<i64>),
+}
+
+/**
+ * The functions and globals provided by the application embedding the machine
+ *
+ * The program declares them with extrn, so their names must be given to the resolver :
+ *
+ * ```
+ * use rsblang::resolver::Resolver;
+ * use rsblang::vm::{library_names, Host, Machine};
+ *
+ * let host = Host::new()
+ *     .function("twice", |_, arguments| Ok(arguments[0] * 2))
+ *     .global("limit", 21);
+ *
+ * let mut program = rsblang::parse("main() { extrn twice, limit; return (twice(limit)); }").unwrap();
+ * let resolution = Resolver::new().with_externals(library_names()).with_externals(host.names()).resolve(&program).unwrap();
+ *
+ * rsblang::lowering::lower(&mut program, &resolution);
+ *
+ * assert_eq!(Ok(42), Machine::with_host(&program, Default::default(), host).unwrap().run());
+ * ```
+ */
+#[derive(Default)]
+pub struct Host<'a> {
+    pub(super) functions: Vec<(Symbol, HostFunction<'a>)>,
+    pub(super) globals: Vec<(Symbol, HostGlobal)>,
+}
+
+impl<'a> Host<'a> {
+    pub fn new() -> Host<'a> {
+        Host::default()
+    }
+
+    /**
+     * Provide a function
+     * The function may call back the functions of the program, but not itself
+     */
+    pub fn function<F>(mut self, name: &str, function: F) -> Host<'a>
+    where
+        F: FnMut(&mut Machine<'a>, &[i64]) -> Result<i64> + 'a
+    {
+        self.functions.push((Symbol::intern(name), Box::new(function)));
+        self
+    }
+
+    /**
+     * Provide a global word, initialized with the given value
+     */
+    pub fn global(mut self, name: &str, value: i64) -> Host<'a> {
+        self.globals.push((Symbol::intern(name), HostGlobal::Word(value)));
+        self
+    }
+
+    /**
+     * Provide a global vector, initialized with the given values
+     */
+    pub fn vector(mut self, name: &str, values: Vec<i64>) -> Host<'a> {
+        self.globals.push((Symbol::intern(name), HostGlobal::Vector(values)));
+        self
+    }
+
+    /**
+     * Get the names of the provided functions and globals, to declare them to the resolver
+     */
+    pub fn names(&self) -> Vec<&'static str> {
+        self.functions.iter().map(|(name, _)| name.as_str())
+            .chain(self.globals.iter().map(|(name, _)| name.as_str()))
+            .collect()
+    }
+}
+
+/**
+ * Conversion of a word given by the program into a Rust value
+ */
+pub trait FromWord: Sized {
+    fn from_word(word: i64, machine: &Machine) -> Result<Self>;
+}
+
+/**
+ * Conversion of a Rust value into a word given to the program
+ */
+pub trait IntoWord {
+    fn into_word(self, machine: &mut Machine) -> Result<i64>;
+}
+
+impl FromWord for i64 {
+    fn from_word(word: i64, _: &Machine) -> Result<Self> {
+        Ok(word)
+    }
+}
+
+impl FromWord for i32 {
+    fn from_word(word: i64, _: &Machine) -> Result<Self> {
+        i32::try_from(word).map_err(|_| RuntimeError::new(format!("The word {} is not a 32 bits integer", word)))
+    }
+}
+
+impl FromWord for usize {
+    fn from_word(word: i64, _: &Machine) -> Result<Self> {
+        usize::try_from(word).map_err(|_| RuntimeError::new(format!("The word {} is not a size", word)))
+    }
+}
+
+impl FromWord for bool {
+    fn from_word(word: i64, _: &Machine) -> Result<Self> {
+        Ok(word != 0)
+    }
+}
+
+/**
+ * The word is the address of a string
+ */
+impl FromWord for String {
+    fn from_word(word: i64, machine: &Machine) -> Result<Self> {
+        machine.read_string(word)
+    }
+}
+
+impl IntoWord for i64 {
+    fn into_word(self, _: &mut Machine) -> Result<i64> {
+        Ok(self)
+    }
+}
+
+impl IntoWord for i32 {
+    fn into_word(self, _: &mut Machine) -> Result<i64> {
+        Ok(self as i64)
+    }
+}
+
+impl IntoWord for usize {
+    fn into_word(self, _: &mut Machine) -> Result<i64> {
+        i64::try_from(self).map_err(|_| RuntimeError::new(format!("The size {} does not fit into a word", self)))
+    }
+}
+
+impl IntoWord for bool {
+    fn into_word(self, _: &mut Machine) -> Result<i64> {
+        Ok(self as i64)
+    }
+}
+
+impl IntoWord for () {
+    fn into_word(self, _: &mut Machine) -> Result<i64> {
+        Ok(0)
+    }
+}
+
+/**
+ * The string is allocated into the heap, and the word is its address
+ * The machine never releases it by itself : the application releases it with Machine::release_string()
+ * once the program no longer uses it, or leaves it allocated until the end of the execution
+ */
+impl IntoWord for &str {
+    fn into_word(self, machine: &mut Machine) -> Result<i64> {
+        machine.allocate_string(self)
+    }
+}
+
+/**
+ * Allocated like &str, see its ownership
+ */
+impl IntoWord for String {
+    fn into_word(self, machine: &mut Machine) -> Result<i64> {
+        machine.allocate_string(&self)
+    }
+}
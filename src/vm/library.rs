@@ -0,0 +1,189 @@
+use crate::layout::EOT;
+use crate::vm::{Machine, NativeFunction, RuntimeError};
+
+type Result<T> = std::result::Result<T, RuntimeError>;
+
+/**
+ * The functions of the runtime library, available to all programs
+ */
+pub(super) const LIBRARY: &[(&str, NativeFunction)] = &[
+    ("putchar", putchar),
+    ("getchar", getchar),
+    ("printf", printf),
+    ("char", char),
+    ("lchar", lchar),
+    ("getvec", getvec),
+    ("rlsevec", rlsevec),
+    ("nargs", nargs),
+    ("exit", exit),
+];
+
+/**
+ * Get the names of the runtime library functions, to declare them with extrn
+ */
+pub fn library_names() -> impl Iterator<Item = &'static str> {
+    LIBRARY.iter().map(|(name, _)| *name)
+}
+
+/**
+ * Get an argument, or 0 if it's not given
+ */
+fn argument(arguments: &[i64], index: usize) -> i64 {
+    arguments.get(index).copied().unwrap_or(0)
+}
+
+/**
+ * Get an argument used as a size or an index
+ */
+fn size(arguments: &[i64], index: usize) -> Result<usize> {
+    let value = argument(arguments, index);
+
+    usize::try_from(value).map_err(|_| RuntimeError::new(format!("Invalid size or index {}", value)))
+}
+
+/**
+ * Write the characters packed into the word, from the highest byte
+ */
+fn write_word(machine: &mut Machine, word: i64) -> Result<()> {
+    let bytes = machine.word_size().unpack_chars(word);
+
+    machine.write(&bytes)
+}
+
+/**
+ * Write the string at the given address, up to its terminator
+ */
+fn write_string(machine: &mut Machine, address: i64) -> Result<()> {
+    let mut bytes = Vec::new();
+
+    for index in 0.. {
+        match machine.memory().char_at(address, index)? {
+            byte if byte as i64 == EOT => break,
+            byte => bytes.push(byte),
+        }
+    }
+
+    machine.write(&bytes)
+}
+
+/**
+ * putchar(c) : write the characters packed into the word c
+ * Returns c
+ */
+fn putchar(machine: &mut Machine, arguments: &[i64]) -> Result<i64> {
+    let word = argument(arguments, 0);
+
+    write_word(machine, word)?;
+
+    Ok(word)
+}
+
+/**
+ * getchar() : read the next character, or "*e" at the end of the input
+ */
+fn getchar(machine: &mut Machine, _: &[i64]) -> Result<i64> {
+    Ok(machine.read()?.map(|byte| byte as i64).unwrap_or(EOT))
+}
+
+/**
+ * printf(format, arguments...) : write the format string, replacing the conversions by the arguments
+ * The conversions are %d (decimal), %o (octal), %c (characters) and %s (string)
+ */
+fn printf(machine: &mut Machine, arguments: &[i64]) -> Result<i64> {
+    let format = argument(arguments, 0);
+    let mut next = 1;
+    let mut index = 0;
+
+    loop {
+        let c = machine.memory().char_at(format, index)?;
+
+        index += 1;
+
+        if c as i64 == EOT {
+            break;
+        }
+
+        if c != b'%' {
+            machine.write(&[c])?;
+            continue;
+        }
+
+        let conversion = machine.memory().char_at(format, index)?;
+        let value = argument(arguments, next);
+
+        match conversion {
+            b'd' => machine.write(value.to_string().as_bytes())?,
+            b'o' => machine.write(format!("{:o}", machine.word_size().unsigned(value)).as_bytes())?,
+            b'c' => write_word(machine, value)?,
+            b's' => write_string(machine, value)?,
+            b'%' => {
+                machine.write(b"%")?;
+                index += 1;
+                continue;
+            },
+            // an unknown conversion is written as is
+            _ => {
+                machine.write(b"%")?;
+                continue;
+            },
+        }
+
+        index += 1;
+        next += 1;
+    }
+
+    Ok(0)
+}
+
+/**
+ * char(string, i) : get the i-th character of a string
+ */
+fn char(machine: &mut Machine, arguments: &[i64]) -> Result<i64> {
+    Ok(machine.memory().char_at(argument(arguments, 0), size(arguments, 1)?)? as i64)
+}
+
+/**
+ * lchar(string, i, c) : replace the i-th character of a string
+ * Returns c
+ */
+fn lchar(machine: &mut Machine, arguments: &[i64]) -> Result<i64> {
+    let c = argument(arguments, 2);
+
+    machine.memory_mut().set_char(argument(arguments, 0), size(arguments, 1)?, c as u8)?;
+
+    Ok(c)
+}
+
+/**
+ * getvec(n) : allocate a vector of n + 1 words, and get its address
+ */
+fn getvec(machine: &mut Machine, arguments: &[i64]) -> Result<i64> {
+    let size = size(arguments, 0)? + 1;
+
+    Ok(machine.memory_mut().allocate_heap(size)? as i64)
+}
+
+/**
+ * rlsevec(v, n) : release a vector allocated by getvec(n)
+ */
+fn rlsevec(machine: &mut Machine, arguments: &[i64]) -> Result<i64> {
+    let size = size(arguments, 1)? + 1;
+
+    machine.memory_mut().release_heap(argument(arguments, 0), size)?;
+
+    Ok(0)
+}
+
+/**
+ * nargs() : get the number of arguments given to the calling function
+ */
+fn nargs(machine: &mut Machine, _: &[i64]) -> Result<i64> {
+    Ok(machine.arguments() as i64)
+}
+
+/**
+ * exit() : terminate the program
+ */
+fn exit(_: &mut Machine, _: &[i64]) -> Result<i64> {
+    Err(RuntimeError::exit())
+}
@@ -0,0 +1,665 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{self, BufReader, Read, Write};
+use std::rc::Rc;
+
+use crate::parser::ast::BinaryOperator;
+use crate::parser::{Opcode, Program, Value, Variable};
+use crate::layout::{pack_chars, WordSize, EOT};
+use crate::tokenizer::Symbol;
+use crate::vm::host::HostGlobal;
+use crate::vm::library::LIBRARY;
+use crate::vm::{FromWord, Host, HostFunction, IntoWord, Memory, RuntimeError};
+
+type Result<T> = std::result::Result<T, RuntimeError>;
+
+/**
+ * A function of the runtime library, called with the machine and the argument words
+ */
+pub type NativeFunction = fn(&mut Machine, &[i64]) -> Result<i64>;
+
+/**
+ * Maximum number of nested calls
+ */
+const MAX_FRAMES: usize = 1 << 16;
+
+enum Callable<'a> {
+    Function {
+        name: Symbol,
+        code: Vec<Opcode>,
+    },
+    /**
+     * A library or host function
+     * It's shared, so it can be called while the machine is borrowed
+     */
+    Native(Rc<RefCell<HostFunction<'a>>>),
+}
+
+/**
+ * A call of a function of the program
+ */
+struct Frame {
+    /**
+     * Index into Machine::callables
+     */
+    function: usize,
+    /**
+     * Index of the next instruction
+     */
+    pc: usize,
+    /**
+     * Address of the first local word
+     */
+    base: usize,
+    /**
+     * Size of the operand stack when the function was called
+     */
+    stack: usize,
+    /**
+     * Number of arguments given by the caller
+     */
+    arguments: usize,
+}
+
+/**
+ * Virtual machine executing a lowered program
+ *
+ * Each global, function and library function has a word in memory. The word of a function
+ * holds its own address, which is the value used to call the function.
+ * All computed values are truncated to the word size.
+ */
+pub struct Machine<'a> {
+    memory: Memory,
+    /**
+     * Address of the word of each global name
+     */
+    globals: HashMap<Symbol, usize>,
+    /**
+     * Address of each string literal
+     */
+    strings: HashMap<String, usize>,
+    callables: Vec<Callable<'a>>,
+    /**
+     * Index into callables of each function value
+     */
+    functions: HashMap<i64, usize>,
+    stack: Vec<i64>,
+    frames: Vec<Frame>,
+    input: Box<dyn Read + 'a>,
+    output: Box<dyn Write + 'a>,
+}
+
+impl<'a> Machine<'a> {
+    /**
+     * Load a lowered program with 64 bits words, see lowering::lower()
+     */
+    pub fn new(program: &Program) -> Result<Machine<'a>> {
+        Self::with_word_size(program, WordSize::default())
+    }
+
+    /**
+     * Load a lowered program, using the given word size
+     */
+    pub fn with_word_size(program: &Program, word_size: WordSize) -> Result<Machine<'a>> {
+        Self::with_host(program, word_size, Host::new())
+    }
+
+    /**
+     * Load a lowered program, with the functions and globals provided by the application
+     * The host functions replace the library functions of the same name
+     */
+    pub fn with_host(program: &Program, word_size: WordSize, host: Host<'a>) -> Result<Machine<'a>> {
+        let mut machine = Machine {
+            memory: Memory::new(word_size),
+            globals: HashMap::new(),
+            strings: HashMap::new(),
+            callables: Vec::new(),
+            functions: HashMap::new(),
+            stack: Vec::new(),
+            frames: Vec::new(),
+            input: Box::new(BufReader::new(io::stdin())),
+            output: Box::new(io::stdout()),
+        };
+
+        for variable in program.globals.values() {
+            // a vector is a word pointing to the following words
+            let size = match variable.vector_size() {
+                Some(size) => size as usize + 1,
+                None => variable.initial().len().max(1),
+            };
+
+            let address = machine.memory.allocate(size)?;
+
+            machine.globals.insert(variable.name(), address);
+        }
+
+        for function in program.functions.values() {
+            if function.statements.is_empty() {
+                return Err(RuntimeError::new(format!("The function `{}` is not lowered", function.name)));
+            }
+
+            machine.define(function.name, Callable::Function { name: function.name, code: function.statements.clone() })?;
+        }
+
+        for (name, function) in host.functions {
+            machine.check_host_name(name)?;
+            machine.define(name, Callable::Native(Rc::new(RefCell::new(function))))?;
+        }
+
+        for (name, global) in host.globals {
+            machine.check_host_name(name)?;
+
+            let address = match global {
+                HostGlobal::Word(value) => {
+                    let address = machine.memory.allocate(1)?;
+
+                    machine.memory.store(address as i64, value)?;
+                    address
+                },
+                HostGlobal::Vector(values) => {
+                    let address = machine.memory.allocate(values.len() + 1)?;
+
+                    machine.memory.store(address as i64, address as i64 + 1)?;
+
+                    for (index, value) in values.into_iter().enumerate() {
+                        machine.memory.store((address + index + 1) as i64, value)?;
+                    }
+
+                    address
+                },
+            };
+
+            machine.globals.insert(name, address);
+        }
+
+        for (name, function) in LIBRARY {
+            let name = Symbol::intern(name);
+
+            if !machine.globals.contains_key(&name) {
+                machine.define(name, Callable::Native(Rc::new(RefCell::new(Box::new(*function)))))?;
+            }
+        }
+
+        // the string literals are allocated once, and the code pushes their address
+        for index in 0..machine.callables.len() {
+            let Callable::Function { code, .. } = &mut machine.callables[index] else {
+                continue;
+            };
+            let mut code = std::mem::take(code);
+
+            for opcode in code.iter_mut() {
+                if let Opcode::String(value) = opcode {
+                    *opcode = Opcode::Constant(machine.string(value)? as i64);
+                }
+            }
+
+            if let Callable::Function { code: loaded, .. } = &mut machine.callables[index] {
+                *loaded = code;
+            }
+        }
+
+        for variable in program.globals.values() {
+            machine.initialize(variable)?;
+        }
+
+        Ok(machine)
+    }
+
+    /**
+     * Read the input of the program from the given reader instead of stdin
+     * The reader should be buffered, since the characters are read one by one
+     */
+    pub fn with_input<R: Read + 'a>(mut self, input: R) -> Machine<'a> {
+        self.input = Box::new(input);
+        self
+    }
+
+    /**
+     * Write the output of the program to the given writer instead of stdout
+     */
+    pub fn with_output<W: Write + 'a>(mut self, output: W) -> Machine<'a> {
+        self.output = Box::new(output);
+        self
+    }
+
+    pub fn word_size(&self) -> WordSize {
+        self.memory.word_size()
+    }
+
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    pub fn memory_mut(&mut self) -> &mut Memory {
+        &mut self.memory
+    }
+
+    /**
+     * Get the address of the word of a global, function or library function
+     */
+    pub fn global(&self, name: &str) -> Option<usize> {
+        self.globals.get(&Symbol::intern(name)).copied()
+    }
+
+    /**
+     * Get the number of arguments given to the executing function
+     */
+    pub fn arguments(&self) -> usize {
+        self.frames.last().map(|frame| frame.arguments).unwrap_or(0)
+    }
+
+    /**
+     * Convert a word given by the program
+     */
+    pub fn from_word<T: FromWord>(&self, word: i64) -> Result<T> {
+        T::from_word(word, self)
+    }
+
+    /**
+     * Convert a value into a word given to the program
+     */
+    pub fn to_word<T: IntoWord>(&mut self, value: T) -> Result<i64> {
+        value.into_word(self)
+    }
+
+    /**
+     * Read the string located at the given address
+     * The characters which are not valid UTF-8 are replaced
+     */
+    pub fn read_string(&self, address: i64) -> Result<String> {
+        let mut bytes = Vec::new();
+
+        for index in 0.. {
+            match self.memory.char_at(address, index)? {
+                byte if byte as i64 == EOT => break,
+                byte => bytes.push(byte),
+            }
+        }
+
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    /**
+     * Allocate a string into the heap, and get its address
+     * The string stays allocated until it's released, see release_string()
+     */
+    pub fn allocate_string(&mut self, string: &str) -> Result<i64> {
+        let words = self.word_size().pack_string(string);
+        let address = self.memory.allocate_heap(words.len())? as i64;
+
+        for (offset, word) in words.into_iter().enumerate() {
+            self.memory.store(address + offset as i64, word)?;
+        }
+
+        Ok(address)
+    }
+
+    /**
+     * Release a string allocated into the heap by allocate_string(), or by converting a string to a word
+     * The string can no longer be used by the program, and its words may be reused by the next allocations
+     */
+    pub fn release_string(&mut self, address: i64) -> Result<()> {
+        let size = self.memory.heap_size(address)
+            .ok_or_else(|| RuntimeError::new(format!("Invalid release of the address {}", address)))?;
+
+        self.memory.release_heap(address, size)
+    }
+
+    /**
+     * Read the words of a vector located at the given address
+     */
+    pub fn read_vector(&self, address: i64, size: usize) -> Result<Vec<i64>> {
+        (0..size as i64).map(|offset| self.memory.load(address.wrapping_add(offset))).collect()
+    }
+
+    /**
+     * Execute the main function
+     * Returns the value returned by main, or 0 if the program calls exit
+     */
+    pub fn run(&mut self) -> Result<i64> {
+        self.call("main", &[])
+    }
+
+    /**
+     * Call a function by name
+     */
+    pub fn call(&mut self, name: &str, arguments: &[i64]) -> Result<i64> {
+        let address = self.global(name).ok_or_else(|| RuntimeError::new(format!("Undefined function `{}`", name)))?;
+        let function = self.memory.load(address as i64)?;
+        let arguments: Vec<i64> = arguments.iter().map(|argument| self.word_size().wrap(*argument)).collect();
+        let depth = self.frames.len();
+
+        let result = match self.invoke(function, &arguments) {
+            Ok(Some(result)) => Ok(result),
+            Ok(None) => self.execute(depth),
+            Err(error) => Err(error),
+        };
+
+        let flushed = self.output.flush().map_err(|e| RuntimeError::new(format!("Cannot write the output: {}", e)));
+        let result = match result {
+            // exit unwinds the calls up to the first one
+            Err(error) if error.is_exit() && depth == 0 => 0,
+            result => result?,
+        };
+
+        flushed?;
+
+        Ok(result)
+    }
+
+    /**
+     * Read a byte from the input of the program
+     * Returns None at the end of the input
+     */
+    pub(super) fn read(&mut self) -> Result<Option<u8>> {
+        let mut byte = [0];
+
+        loop {
+            return match self.input.read(&mut byte) {
+                Ok(0) => Ok(None),
+                Ok(_) => Ok(Some(byte[0])),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => Err(RuntimeError::new(format!("Cannot read the input: {}", e))),
+            };
+        }
+    }
+
+    /**
+     * Write bytes to the output of the program
+     */
+    pub(super) fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        self.output.write_all(bytes).map_err(|e| RuntimeError::new(format!("Cannot write the output: {}", e)))
+    }
+
+    /**
+     * Allocate the word of a function, holding its own address
+     */
+    fn define(&mut self, name: Symbol, callable: Callable<'a>) -> Result<()> {
+        let address = self.memory.allocate(1)?;
+
+        self.memory.store(address as i64, address as i64)?;
+        self.globals.insert(name, address);
+        self.functions.insert(address as i64, self.callables.len());
+        self.callables.push(callable);
+
+        Ok(())
+    }
+
+    fn check_host_name(&self, name: Symbol) -> Result<()> {
+        match self.globals.contains_key(&name) {
+            true => Err(RuntimeError::new(format!("The host defines `{}`, which is already defined", name))),
+            false => Ok(()),
+        }
+    }
+
+    /**
+     * Get the address of a string literal, allocated on first use
+     */
+    fn string(&mut self, value: &str) -> Result<usize> {
+        if let Some(address) = self.strings.get(value) {
+            return Ok(*address);
+        }
+
+        let address = self.memory.allocate_string(value)?;
+
+        self.strings.insert(value.to_string(), address);
+
+        Ok(address)
+    }
+
+    fn initialize(&mut self, variable: &Variable) -> Result<()> {
+        let mut address = self.globals[&variable.name()] as i64;
+
+        if let Variable::Array { .. } = variable {
+            self.memory.store(address, address + 1)?;
+            address += 1;
+        }
+
+        for (index, value) in variable.initial().iter().enumerate() {
+            let word = match value {
+                Value::Constant(value) => *value as i64,
+                Value::Char(chars) => pack_chars(chars),
+                Value::String(value) => self.string(value)? as i64,
+                Value::Variable(name) => *self.globals.get(name)
+                    .ok_or_else(|| RuntimeError::new(format!("Undefined global `{}`", name)))? as i64,
+            };
+
+            self.memory.store(address + index as i64, word)?;
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Call a function value
+     * A library function is executed immediately, and its result is returned
+     * For a function of the program, a frame is pushed and None is returned
+     */
+    fn invoke(&mut self, function: i64, arguments: &[i64]) -> Result<Option<i64>> {
+        let index = *self.functions.get(&function)
+            .ok_or_else(|| RuntimeError::new(format!("Call of the value {} which is not a function", function)))?;
+
+        if let Callable::Native(native) = &self.callables[index] {
+            let native = Rc::clone(native);
+            let mut function = native.try_borrow_mut()
+                .map_err(|_| RuntimeError::new(String::from("Recursive call of a host function")))?;
+
+            return function(self, arguments).map(|result| Some(self.word_size().wrap(result)));
+        }
+
+        if self.frames.len() >= MAX_FRAMES {
+            return Err(RuntimeError::new(String::from("Stack overflow")));
+        }
+
+        let base = self.memory.allocate(arguments.len())?;
+
+        for (offset, argument) in arguments.iter().enumerate() {
+            self.memory.store((base + offset) as i64, *argument)?;
+        }
+
+        self.frames.push(Frame {
+            function: index,
+            pc: 0,
+            base,
+            stack: self.stack.len(),
+            arguments: arguments.len(),
+        });
+
+        Ok(None)
+    }
+
+    /**
+     * Execute instructions until the frame at the given depth returns
+     */
+    fn execute(&mut self, depth: usize) -> Result<i64> {
+        loop {
+            match self.step() {
+                Ok(Some(result)) if self.frames.len() == depth => return Ok(result),
+                Ok(Some(result)) => self.stack.push(result),
+                Ok(None) => {},
+                Err(error) => {
+                    let function = match self.frames.last().map(|frame| &self.callables[frame.function]) {
+                        Some(Callable::Function { name, .. }) => Some(*name),
+                        _ => None,
+                    };
+
+                    // the frames of the failed call are discarded
+                    if let Some(frame) = self.frames.get(depth) {
+                        self.memory.release(frame.base);
+                        self.stack.truncate(frame.stack);
+                        self.frames.truncate(depth);
+                    }
+
+                    return Err(match function {
+                        Some(name) => error.in_function(name.as_str()),
+                        None => error,
+                    });
+                },
+            }
+        }
+    }
+
+    /**
+     * Execute the next instruction
+     * Returns the returned value when the current function returns
+     */
+    fn step(&mut self) -> Result<Option<i64>> {
+        let frame = self.frames.last_mut().expect("A function is executing");
+        let Callable::Function { code, .. } = &self.callables[frame.function] else {
+            unreachable!("Library functions have no frame");
+        };
+        let opcode = code.get(frame.pc).cloned()
+            .ok_or_else(|| RuntimeError::new(format!("Invalid instruction index {}", frame.pc)))?;
+        let base = frame.base as i64;
+
+        frame.pc += 1;
+
+        match opcode {
+            Opcode::Enter(size) => {
+                let arguments = frame.arguments;
+
+                if size > arguments {
+                    self.memory.allocate(size - arguments)?;
+                }
+            },
+            Opcode::Vector { local, size } => {
+                let address = self.memory.allocate(size)?;
+
+                self.memory.store(base + local as i64, address as i64)?;
+            },
+            Opcode::Constant(value) => self.stack.push(self.memory.word_size().wrap(value)),
+            Opcode::String(_) => unreachable!("The strings are replaced by their address when loading the program"),
+            Opcode::Local(index) => self.stack.push(base + index as i64),
+            Opcode::Global(name) => {
+                let address = *self.globals.get(&name)
+                    .ok_or_else(|| RuntimeError::new(format!("Undefined global `{}`", name)))?;
+
+                self.stack.push(address as i64);
+            },
+            Opcode::Load => {
+                let address = self.pop()?;
+
+                self.stack.push(self.memory.load(address)?);
+            },
+            Opcode::Store => {
+                let value = self.pop()?;
+                let address = self.pop()?;
+
+                self.memory.store(address, value)?;
+                self.stack.push(value);
+            },
+            Opcode::Increment(delta) | Opcode::PostIncrement(delta) => {
+                let address = self.pop()?;
+                let previous = self.memory.load(address)?;
+                let value = self.memory.word_size().wrap(previous.wrapping_add(delta));
+
+                self.memory.store(address, value)?;
+                self.stack.push(if let Opcode::Increment(_) = opcode { value } else { previous });
+            },
+            Opcode::Duplicate => {
+                let value = self.pop()?;
+
+                self.stack.extend([value, value]);
+            },
+            Opcode::Pop => {
+                self.pop()?;
+            },
+            Opcode::Negate => {
+                let value = self.pop()?;
+
+                self.stack.push(self.memory.word_size().wrap(value.wrapping_neg()));
+            },
+            Opcode::Not => {
+                let value = self.pop()?;
+
+                self.stack.push((value == 0) as i64);
+            },
+            Opcode::Binary(operator) => {
+                let right = self.pop()?;
+                let left = self.pop()?;
+
+                let result = Self::binary(operator, left, right, self.memory.word_size())?;
+
+                self.stack.push(self.memory.word_size().wrap(result));
+            },
+            Opcode::Jump(target) => self.jump(target as i64),
+            Opcode::JumpIfZero(target) => {
+                if self.pop()? == 0 {
+                    self.jump(target as i64);
+                }
+            },
+            Opcode::JumpIndirect => {
+                let target = self.pop()?;
+
+                self.jump(target);
+            },
+            Opcode::Case(value, target) => {
+                // the constant is wrapped like the value, so 0177777 matches -1 on 16 bits
+                if self.stack.last() == Some(&self.memory.word_size().wrap(value)) {
+                    self.stack.pop();
+                    self.jump(target as i64);
+                }
+            },
+            Opcode::Call(count) => {
+                let arguments = self.stack.split_off(self.stack.len().checked_sub(count).ok_or_else(Self::underflow)?);
+                let function = self.pop()?;
+
+                return self.invoke(function, &arguments);
+            },
+            Opcode::Return => {
+                let value = self.pop()?;
+                let frame = self.frames.pop().expect("A function is executing");
+
+                self.memory.release(frame.base);
+                self.stack.truncate(frame.stack);
+
+                return Ok(Some(value));
+            },
+        }
+
+        Ok(None)
+    }
+
+    /**
+     * Set the next instruction of the current function
+     * An invalid target is reported when the instruction is fetched
+     */
+    fn jump(&mut self, target: i64) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.pc = usize::try_from(target).unwrap_or(usize::MAX);
+        }
+    }
+
+    fn pop(&mut self) -> Result<i64> {
+        self.stack.pop().ok_or_else(Self::underflow)
+    }
+
+    fn underflow() -> RuntimeError {
+        RuntimeError::new(String::from("Stack underflow"))
+    }
+
+    fn binary(operator: BinaryOperator, left: i64, right: i64, word_size: WordSize) -> Result<i64> {
+        Ok(match operator {
+            BinaryOperator::Or => left | right,
+            BinaryOperator::And => left & right,
+            BinaryOperator::Equal => (left == right) as i64,
+            BinaryOperator::NotEqual => (left != right) as i64,
+            BinaryOperator::Less => (left < right) as i64,
+            BinaryOperator::LessEqual => (left <= right) as i64,
+            BinaryOperator::Greater => (left > right) as i64,
+            BinaryOperator::GreaterEqual => (left >= right) as i64,
+            // shifting by the word size or more clears the word
+            BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight if !(0..word_size.bits() as i64).contains(&right) => 0,
+            BinaryOperator::ShiftLeft => left << right,
+            BinaryOperator::ShiftRight => left >> right,
+            BinaryOperator::Add => left.wrapping_add(right),
+            BinaryOperator::Subtract => left.wrapping_sub(right),
+            BinaryOperator::Multiply => left.wrapping_mul(right),
+            BinaryOperator::Divide | BinaryOperator::Modulo if right == 0 => {
+                return Err(RuntimeError::new(String::from("Division by zero")));
+            },
+            BinaryOperator::Divide => left.wrapping_div(right),
+            BinaryOperator::Modulo => left.wrapping_rem(right),
+        })
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+
+use crate::layout::WordSize;
+use crate::vm::RuntimeError;
+
+type Result<T> = std::result::Result<T, RuntimeError>;
+
+/**
+ * The word-addressed memory of the machine, see WordSize for the layout of the values
+ *
+ * The globals and the string literals are allocated first, then the memory grows and shrinks
+ * with the frames of the called functions.
+ * The vectors allocated by the program (using getvec) are stored into the heap, which grows
+ * down from the highest address.
+ * The address 0 is the null pointer, it's never allocated.
+ */
+#[derive(Debug, Clone)]
+pub struct Memory {
+    word_size: WordSize,
+    words: Vec<i64>,
+    /**
+     * The words of the heap, from the highest address
+     */
+    heap: Vec<i64>,
+    /**
+     * Allocated blocks of the heap : the size indexed by the address of the first word
+     */
+    allocated: HashMap<usize, usize>,
+    /**
+     * Released blocks of the heap : the address of the first word, and the size
+     */
+    free: Vec<(usize, usize)>,
+}
+
+impl Memory {
+    pub fn new(word_size: WordSize) -> Memory {
+        Memory {
+            word_size,
+            words: vec![0],
+            heap: Vec::new(),
+            allocated: HashMap::new(),
+            free: Vec::new(),
+        }
+    }
+
+    pub fn word_size(&self) -> WordSize {
+        self.word_size
+    }
+
+    /**
+     * Get the number of words, including the null word and excluding the heap
+     */
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.len() <= 1
+    }
+
+    /**
+     * Allocate zeroed words at the end of the memory, and get the address of the first one
+     */
+    pub fn allocate(&mut self, size: usize) -> Result<usize> {
+        let address = self.words.len();
+
+        if size > self.available() {
+            return Err(Self::out_of_memory());
+        }
+
+        self.words.resize(address + size, 0);
+
+        Ok(address)
+    }
+
+    /**
+     * Release all words from the given address
+     */
+    pub(super) fn release(&mut self, address: usize) {
+        self.words.truncate(address.max(1));
+    }
+
+    /**
+     * Allocate zeroed words into the heap, reusing a released block if possible
+     * The block must be released with the same size, see release_heap()
+     */
+    pub fn allocate_heap(&mut self, size: usize) -> Result<usize> {
+        let address = self.allocate_block(Self::block_size(size))?;
+
+        self.allocated.insert(address, size);
+
+        Ok(address)
+    }
+
+    /**
+     * Release a block of the heap, allocated by allocate_heap() with the same size
+     * A block can be released only once
+     */
+    pub fn release_heap(&mut self, address: i64, size: usize) -> Result<()> {
+        let Some(allocated) = self.heap_size(address) else {
+            return Err(RuntimeError::new(format!("Invalid release of the address {}", address)));
+        };
+
+        if allocated != size {
+            return Err(RuntimeError::new(format!(
+                "Invalid release of {} words at the address {}, allocated with {} words", size, address, allocated
+            )));
+        }
+
+        // the address is allocated, so it's a valid index
+        let address = address as usize;
+
+        self.allocated.remove(&address);
+        self.free.push((address, Self::block_size(size)));
+
+        Ok(())
+    }
+
+    /**
+     * Get the size of the block allocated into the heap at the given address
+     * Returns None if no block is allocated at this address
+     */
+    pub fn heap_size(&self, address: i64) -> Option<usize> {
+        usize::try_from(address).ok().and_then(|address| self.allocated.get(&address).copied())
+    }
+
+    pub fn load(&self, address: i64) -> Result<i64> {
+        match self.location(address) {
+            Some(Location::Memory(index)) => Ok(self.words[index]),
+            Some(Location::Heap(index)) => Ok(self.heap[index]),
+            None => Err(Self::invalid_access(address)),
+        }
+    }
+
+    /**
+     * Store a value, truncated to the word size
+     */
+    pub fn store(&mut self, address: i64, value: i64) -> Result<()> {
+        let value = self.word_size.wrap(value);
+        let word = match self.location(address) {
+            Some(Location::Memory(index)) => &mut self.words[index],
+            Some(Location::Heap(index)) => &mut self.heap[index],
+            None => return Err(Self::invalid_access(address)),
+        };
+
+        *word = value;
+
+        Ok(())
+    }
+
+    /**
+     * Allocate a packed string
+     */
+    pub fn allocate_string(&mut self, string: &str) -> Result<usize> {
+        let words = self.word_size.pack_string(string);
+        let address = self.allocate(words.len())?;
+
+        self.words[address..].copy_from_slice(&words);
+
+        Ok(address)
+    }
+
+    /**
+     * Get a character of a packed string
+     */
+    pub fn char_at(&self, address: i64, index: usize) -> Result<u8> {
+        let (offset, shift) = self.word_size.char_location(index);
+        let word = self.load(Self::offset(address, offset)?)?;
+
+        Ok((word >> shift) as u8)
+    }
+
+    /**
+     * Replace a character of a packed string
+     */
+    pub fn set_char(&mut self, address: i64, index: usize, c: u8) -> Result<()> {
+        let (offset, shift) = self.word_size.char_location(index);
+        let address = Self::offset(address, offset)?;
+        let word = self.load(address)?;
+
+        self.store(address, (word & !(0xFF << shift)) | ((c as i64) << shift))
+    }
+
+    /**
+     * Allocate zeroed words into the heap, without recording the block
+     */
+    fn allocate_block(&mut self, size: usize) -> Result<usize> {
+        if let Some(block) = self.free.iter().position(|(_, free)| *free >= size) {
+            let (address, free) = self.free.remove(block);
+
+            if free > size {
+                self.free.push((address + size, free - size));
+            }
+
+            for offset in 0..size {
+                self.store((address + offset) as i64, 0)?;
+            }
+
+            return Ok(address);
+        }
+
+        if size > self.available() {
+            return Err(Self::out_of_memory());
+        }
+
+        self.heap.resize(self.heap.len() + size, 0);
+
+        Ok(self.heap_start())
+    }
+
+    /**
+     * Get the number of words of the heap block allocated for the given size
+     * A block has at least one word, so each allocated block has its own address
+     */
+    fn block_size(size: usize) -> usize {
+        size.max(1)
+    }
+
+    /**
+     * Get the number of words which can still be allocated, into the memory or the heap
+     */
+    fn available(&self) -> usize {
+        self.word_size.address_limit() - self.words.len() - self.heap.len()
+    }
+
+    /**
+     * Get the lowest address of the heap
+     */
+    fn heap_start(&self) -> usize {
+        self.word_size.address_limit() - self.heap.len()
+    }
+
+    fn location(&self, address: i64) -> Option<Location> {
+        let address = usize::try_from(address).ok()?;
+        let limit = self.word_size.address_limit();
+
+        if address > 0 && address < self.words.len() {
+            Some(Location::Memory(address))
+        } else if address >= self.heap_start() && address < limit {
+            // the heap is stored from the highest address
+            Some(Location::Heap(limit - 1 - address))
+        } else {
+            None
+        }
+    }
+
+    fn offset(address: i64, offset: usize) -> Result<i64> {
+        i64::try_from(offset).ok()
+            .and_then(|offset| address.checked_add(offset))
+            .ok_or_else(|| Self::invalid_access(address))
+    }
+
+    fn out_of_memory() -> RuntimeError {
+        RuntimeError::new(String::from("Out of memory"))
+    }
+
+    fn invalid_access(address: i64) -> RuntimeError {
+        RuntimeError::new(format!("Invalid memory access at address {}", address))
+    }
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Memory::new(WordSize::default())
+    }
+}
+
+/**
+ * Index of an address into the words of the memory or of the heap
+ */
+enum Location {
+    Memory(usize),
+    Heap(usize),
+}